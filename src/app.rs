@@ -1,45 +1,114 @@
-use crate::models::{AppEvent, AppState, Config, Job, JobStatus, JobUpdate};
+use crate::config;
+use crate::deps::{self, Dependencies, COOKIE_BROWSERS};
+use crate::downloader::{expand_playlist, get_video_info, is_http_url, list_formats};
+use crate::export;
+use crate::history;
+use crate::models::{
+    AppEvent, AppState, ClipRange, Config, Job, JobStatus, JobUpdate, ListMode, Overlay, Setting,
+    Severity, StageKind, StageStatus, MAX_CONCURRENT_DOWNLOADS, QUALITY_PRESETS,
+};
+use crate::notify;
 use crate::queue::JobQueue;
+use crate::theme::Theme;
+use crate::thumbnail;
 use crate::ui;
+use crate::webhook;
 use arboard::Clipboard;
 use color_eyre::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+};
+use crossterm::terminal::SetTitle;
 use ratatui::DefaultTerminal;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::{mpsc, Mutex};
 
+/// How often the config file is checked for changes
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often unavailable output directories are checked for coming back
+const OUTPUT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
 pub struct App {
     state: Arc<Mutex<AppState>>,
     queue: JobQueue,
     event_tx: mpsc::UnboundedSender<AppEvent>,
     event_rx: mpsc::UnboundedReceiver<AppEvent>,
+    job_update_tx: mpsc::UnboundedSender<(uuid::Uuid, JobUpdate)>,
     job_update_rx: mpsc::UnboundedReceiver<(uuid::Uuid, JobUpdate)>,
     event_task: Option<tokio::task::JoinHandle<()>>,
     shutdown: Arc<AtomicBool>,
+    /// Kept alive for the whole session, as on X11 copied text is only
+    /// available while the clipboard that set it exists
+    clipboard: Option<Clipboard>,
+    config_watch: ConfigWatch,
+    /// When unavailable output directories were last checked
+    output_checked_at: Instant,
+    /// Terminal title as last set
+    title: String,
+    /// Whether the whole screen is drawn again on the next frame
+    redraw: bool,
+}
+
+/// Polls the config file for changes made outside of carbon
+struct ConfigWatch {
+    checked_at: Instant,
+    /// Modification time of the config as last read
+    modified: Option<SystemTime>,
+    /// A newer modification time seen on the previous check, read once it
+    /// stays the same so a file that's still being written isn't read
+    pending: Option<SystemTime>,
+    /// The config as last read, to tell settings changed this session apart
+    read: Config,
 }
 
 impl App {
-    pub fn new(config: Config) -> Self {
+    pub fn new(config: Config, dependencies: Dependencies) -> Self {
         let (event_tx, event_rx) = mpsc::unbounded_channel();
         let (job_update_tx, job_update_rx) = mpsc::unbounded_channel();
 
-        let state = Arc::new(Mutex::new(AppState::new(config.clone())));
-        let queue = JobQueue::new(config.max_concurrent_downloads, job_update_tx, config);
+        // Pick up where the previous session left off
+        let mut app_state = AppState::new(config.clone());
+        app_state.jobs = config::load_jobs().unwrap_or_default();
+        app_state.dependencies = dependencies;
+        app_state.lifetime_stats = config::load_stats().unwrap_or_default();
+        let state = Arc::new(Mutex::new(app_state));
+        let queue = JobQueue::new(
+            config.max_concurrent_downloads,
+            job_update_tx.clone(),
+            config.with_active_profile(),
+        );
 
         Self {
             state,
             queue,
             event_tx,
             event_rx,
+            job_update_tx,
             job_update_rx,
             event_task: None,
             shutdown: Arc::new(AtomicBool::new(false)),
+            clipboard: Clipboard::new().ok(),
+            title: String::new(),
+            redraw: false,
+            output_checked_at: Instant::now(),
+            config_watch: ConfigWatch {
+                checked_at: Instant::now(),
+                modified: config::config_modified(),
+                pending: None,
+                read: config,
+            },
         }
     }
 
     pub async fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        self.spawn_update_check().await;
+
         // Spawn event handler task
         let event_tx = self.event_tx.clone();
         let state = self.state.clone();
@@ -54,26 +123,56 @@ impl App {
                     break;
                 }
 
-                if event::poll(Duration::from_millis(100)).unwrap_or(false) {
-                    if let Ok(event) = event::read() {
-                        if let Event::Key(key) = event {
-                            if key.kind == KeyEventKind::Press {
-                                // Get state info for key mapping
-                                let (input_empty, has_jobs) = {
-                                    let state = state.lock().await;
-                                    (state.input_buffer.is_empty(), state.has_jobs())
-                                };
-
-                                let app_event =
-                                    Self::map_key_event(key, input_empty, has_jobs, &mut clipboard);
-                                if let Some(evt) = app_event {
-                                    if event_tx.send(evt).is_err() {
-                                        break;
-                                    }
-                                }
+                let poll_rate = state.lock().await.config.poll_rate_ms;
+                if !event::poll(Duration::from_millis(poll_rate)).unwrap_or(false) {
+                    continue;
+                }
+
+                match event::read() {
+                    Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                        // Get state info for key mapping
+                        let (input_empty, has_jobs, overlay, blocked) = {
+                            let state = state.lock().await;
+                            (
+                                state.input_buffer.is_empty(),
+                                state.has_jobs(),
+                                state.overlay,
+                                state.missing_dependencies(),
+                            )
+                        };
+
+                        // Quitting is all there is to do without yt-dlp or ffmpeg
+                        let app_event = if blocked {
+                            matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                                .then_some(AppEvent::Quit)
+                        } else {
+                            Self::map_key_event(key, input_empty, has_jobs, overlay, &mut clipboard)
+                        };
+                        if let Some(evt) = app_event {
+                            if event_tx.send(evt).is_err() {
+                                break;
                             }
                         }
                     }
+                    // A closed channel ends the loop on the next key or shutdown
+                    Ok(Event::Resize(..)) => {
+                        let _ = event_tx.send(AppEvent::Resize);
+                    }
+                    Ok(Event::Mouse(mouse)) => {
+                        let (overlay, blocked) = {
+                            let state = state.lock().await;
+                            (state.overlay, state.missing_dependencies())
+                        };
+                        let app_event = (!blocked)
+                            .then(|| Self::map_mouse_event(mouse, overlay))
+                            .flatten();
+                        if let Some(evt) = app_event {
+                            if event_tx.send(evt).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    _ => {}
                 }
             }
         });
@@ -87,28 +186,203 @@ impl App {
                 self.apply_job_update(job_id, update).await;
             }
 
+            // Sum up the batch once its last job is done
+            let batch_done = {
+                let mut state = self.state.lock().await;
+                state.check_batch_done() && state.config.bell_on_batch_complete
+            };
+            if batch_done {
+                ring_bell();
+            }
+
             // Handle events
             while let Ok(event) = self.event_rx.try_recv() {
                 if !self.handle_event(event).await? {
-                    // Signal shutdown and wait for the event task to finish
-                    self.shutdown.store(true, Ordering::Relaxed);
-                    if let Some(task) = self.event_task.take() {
-                        let _ = task.await;
-                    }
+                    self.stop().await;
                     return Ok(());
                 }
             }
 
+            // Quit once the jobs left to finish are done
+            let finished = {
+                let state = self.state.lock().await;
+                let finished = state.finishing && state.active_jobs_count() == 0;
+                if finished {
+                    Self::save_jobs(&state);
+                }
+                finished
+            };
+            if finished {
+                self.stop().await;
+                return Ok(());
+            }
+
+            self.retry_available_output().await;
+            self.fail_stalled_jobs().await;
+
+            self.reload_changed_config().await;
+
+            // Some terminals keep parts of the old frame after resizing
+            if std::mem::take(&mut self.redraw) {
+                terminal.clear()?;
+            }
+
             // Render UI
-            let state = self.state.lock().await;
-            terminal.draw(|frame| ui::render(frame, &state))?;
+            // Rendering also updates the list's scroll position
+            let mut state = self.state.lock().await;
+            state.expire_status();
+            state.tick = state.tick.wrapping_add(1);
+            terminal.draw(|frame| ui::render(frame, &mut state))?;
+            let title = window_title(&state);
             drop(state);
 
+            // Only written when it changes, some terminals redraw on every write
+            if title != self.title {
+                let _ = crossterm::execute!(std::io::stdout(), SetTitle(&title));
+                self.title = title;
+            }
+
             // Process queued jobs
             self.process_queue().await;
 
-            // Small delay to prevent CPU spinning
-            tokio::time::sleep(Duration::from_millis(50)).await;
+            // Small delay to prevent CPU spinning, longer ones save battery
+            let tick_rate = self.state.lock().await.config.tick_rate_ms;
+            tokio::time::sleep(Duration::from_millis(tick_rate)).await;
+        }
+    }
+
+    /// Queue jobs that failed on an unavailable output directory again once
+    /// it's back, e.g. when the drive is plugged in again
+    async fn retry_available_output(&mut self) {
+        if self.output_checked_at.elapsed() < OUTPUT_CHECK_INTERVAL {
+            return;
+        }
+        self.output_checked_at = Instant::now();
+
+        let mut state = self.state.lock().await;
+        let base_dir = PathBuf::from(&state.config.output_directory);
+        let mut retried = 0;
+        for job in state.jobs.iter_mut().filter(|j| j.status().is_failed()) {
+            let available = job
+                .output_unavailable
+                .as_deref()
+                .is_some_and(|dir| config::output_available(&base_dir, dir));
+            if available {
+                job.reset_for_retry();
+                retried += 1;
+            }
+        }
+        if retried > 0 {
+            state.set_status(
+                Severity::Info,
+                format!("output directory is back, retrying {} jobs", retried),
+            );
+        }
+    }
+
+    /// Fail downloads that haven't reported anything for the stall timeout,
+    /// killing yt-dlp. Their partial file is kept for retrying.
+    async fn fail_stalled_jobs(&mut self) {
+        let state = self.state.lock().await;
+        let timeout = Duration::from_secs(state.config.stall_timeout_secs);
+        if timeout.is_zero() {
+            return;
+        }
+        let stalled: Vec<_> = state
+            .jobs
+            .iter()
+            .filter(|j| j.status() == JobStatus::Downloading)
+            .filter(|j| j.last_progress_at.is_some_and(|t| t.elapsed() > timeout))
+            .map(|j| j.id)
+            .collect();
+        drop(state);
+
+        for job_id in stalled {
+            tracing::warn!(job = %job_id, "download stalled");
+            self.queue.stop_job(job_id);
+            let error = format!("Download stalled: no progress for {}s", timeout.as_secs());
+            self.apply_job_update(job_id, JobUpdate::Error(StageKind::Download, error))
+                .await;
+            self.apply_job_update(
+                job_id,
+                JobUpdate::Stage(StageKind::Download, StageStatus::Failed),
+            )
+            .await;
+        }
+    }
+
+    /// Reload the config once a second if it was changed on disk. Everything
+    /// applies to jobs started from now on
+    async fn reload_changed_config(&mut self) {
+        let watch = &mut self.config_watch;
+        if watch.checked_at.elapsed() < CONFIG_CHECK_INTERVAL {
+            return;
+        }
+        watch.checked_at = Instant::now();
+
+        let modified = config::config_modified();
+        if modified.is_none() || modified == watch.modified {
+            watch.pending = None;
+            return;
+        }
+        if watch.pending != modified {
+            watch.pending = modified;
+            return;
+        }
+        watch.modified = modified;
+        watch.pending = None;
+
+        let mut state = self.state.lock().await;
+        match config::read_config() {
+            Ok((mut config, warnings)) => {
+                let read = std::mem::replace(&mut watch.read, config.clone());
+                keep_session_settings(&read, &state.config, &mut config);
+
+                // Saving from the settings editor changes the file too
+                let unchanged =
+                    toml::to_string(&config).ok() == toml::to_string(&state.config).ok();
+                if !unchanged {
+                    let active = config.with_active_profile();
+                    state.selected_quality = active.default_quality.clone();
+                    state.theme = Theme::from_config(&config.theme);
+                    state.config = config;
+                    self.queue.set_config(active);
+                    state.set_status(Severity::Info, "config reloaded");
+                }
+
+                for warning in &warnings {
+                    tracing::warn!("config: {}", warning);
+                }
+                if !warnings.is_empty() {
+                    state.set_status(Severity::Warn, format!("config: {}", warnings.join("; ")));
+                }
+            }
+            Err(e) => state.set_status(Severity::Error, format!("config not reloaded: {}", e)),
+        }
+    }
+
+    /// Stop the event task and any jobs still running
+    async fn stop(&mut self) {
+        // Signal shutdown and wait for the event task to finish
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(task) = self.event_task.take() {
+            let _ = task.await;
+        }
+
+        let jobs = self.state.lock().await.jobs.clone();
+        self.queue.shutdown(&jobs).await;
+    }
+
+    /// The wheel scrolls through jobs, or the list of an open picker.
+    /// Clicking selects a job while no overlay is open.
+    fn map_mouse_event(mouse: MouseEvent, overlay: Option<Overlay>) -> Option<AppEvent> {
+        match mouse.kind {
+            MouseEventKind::ScrollUp => Some(AppEvent::MoveUp),
+            MouseEventKind::ScrollDown => Some(AppEvent::MoveDown),
+            MouseEventKind::Down(MouseButton::Left) if overlay.is_none() => {
+                Some(AppEvent::ClickRow(mouse.row))
+            }
+            _ => None,
         }
     }
 
@@ -116,8 +390,111 @@ impl App {
         key: KeyEvent,
         input_empty: bool,
         has_jobs: bool,
+        overlay: Option<Overlay>,
         clipboard: &mut Option<Clipboard>,
     ) -> Option<AppEvent> {
+        // Overlays swallow all keys they don't handle
+        match overlay {
+            Some(Overlay::Details { .. }) => {
+                return match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => Some(AppEvent::MoveUp),
+                    KeyCode::Down | KeyCode::Char('j') => Some(AppEvent::MoveDown),
+                    KeyCode::Esc | KeyCode::Enter => Some(AppEvent::CloseOverlay),
+                    _ => None,
+                };
+            }
+            Some(Overlay::QualityPicker { .. }) => {
+                return match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => Some(AppEvent::MoveUp),
+                    KeyCode::Down | KeyCode::Char('j') => Some(AppEvent::MoveDown),
+                    KeyCode::Char('d') => Some(AppEvent::ToggleDeleteOriginal),
+                    KeyCode::Enter => Some(AppEvent::ConfirmRequeue),
+                    KeyCode::Esc => Some(AppEvent::CloseOverlay),
+                    _ => None,
+                };
+            }
+            Some(Overlay::BrowserPicker { .. }) => {
+                return match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => Some(AppEvent::MoveUp),
+                    KeyCode::Down | KeyCode::Char('j') => Some(AppEvent::MoveDown),
+                    KeyCode::Enter => Some(AppEvent::ConfirmBrowser),
+                    KeyCode::Esc => Some(AppEvent::CloseOverlay),
+                    _ => None,
+                };
+            }
+            Some(Overlay::Formats { .. }) => {
+                return match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => Some(AppEvent::MoveUp),
+                    KeyCode::Down | KeyCode::Char('j') => Some(AppEvent::MoveDown),
+                    KeyCode::Enter => Some(AppEvent::ConfirmFormat),
+                    KeyCode::Esc => Some(AppEvent::CloseOverlay),
+                    _ => None,
+                };
+            }
+            Some(Overlay::ConfirmQuit) => {
+                return match key.code {
+                    KeyCode::Char('q') | KeyCode::Char('y') => Some(AppEvent::Quit),
+                    KeyCode::Esc | KeyCode::Char('n') => Some(AppEvent::CloseOverlay),
+                    _ => None,
+                };
+            }
+            Some(Overlay::BatchSummary { .. }) => {
+                return match key.code {
+                    KeyCode::Esc | KeyCode::Enter => Some(AppEvent::CloseOverlay),
+                    _ => None,
+                };
+            }
+            Some(Overlay::Help) => {
+                return match key.code {
+                    KeyCode::Char('?') | KeyCode::Esc => Some(AppEvent::ToggleHelp),
+                    _ => None,
+                };
+            }
+            Some(Overlay::Settings { editing: true, .. }) => {
+                return match key.code {
+                    KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        let text = clipboard.as_mut().and_then(|c| c.get_text().ok());
+                        text.map(AppEvent::InputPaste)
+                    }
+                    KeyCode::Enter => Some(AppEvent::EditSetting),
+                    KeyCode::Esc => Some(AppEvent::CloseOverlay),
+                    KeyCode::Backspace => Some(AppEvent::InputBackspace),
+                    KeyCode::Char(c) => Some(AppEvent::InputChar(c)),
+                    _ => None,
+                };
+            }
+            Some(Overlay::Filter) => {
+                return match key.code {
+                    KeyCode::Enter => Some(AppEvent::CloseOverlay),
+                    KeyCode::Esc => Some(AppEvent::ClearFilter),
+                    KeyCode::Backspace => Some(AppEvent::InputBackspace),
+                    KeyCode::Char(c) => Some(AppEvent::InputChar(c)),
+                    _ => None,
+                };
+            }
+            Some(Overlay::Settings { editing: false, .. }) => {
+                return match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => Some(AppEvent::MoveUp),
+                    KeyCode::Down | KeyCode::Char('j') => Some(AppEvent::MoveDown),
+                    KeyCode::Enter => Some(AppEvent::EditSetting),
+                    KeyCode::Esc | KeyCode::Char('s') => Some(AppEvent::CloseOverlay),
+                    _ => None,
+                };
+            }
+            None => {}
+        }
+
+        // Ctrl+Shift+V pastes and submits right away, where the terminal
+        // doesn't take it for its own paste
+        if matches!(key.code, KeyCode::Char('v' | 'V'))
+            && key
+                .modifiers
+                .contains(KeyModifiers::CONTROL | KeyModifiers::SHIFT)
+        {
+            let text = clipboard.as_mut().and_then(|c| c.get_text().ok());
+            return text.map(AppEvent::PasteSubmit);
+        }
+
         // Handle Ctrl+V for paste (always available)
         if key.code == KeyCode::Char('v') && key.modifiers.contains(KeyModifiers::CONTROL) {
             if let Some(clipboard) = clipboard {
@@ -137,6 +514,46 @@ impl App {
                     Some(AppEvent::InputChar('q'))
                 }
             }
+            // Help only opens when input is empty
+            KeyCode::Char('?') => {
+                if input_empty {
+                    Some(AppEvent::ToggleHelp)
+                } else {
+                    Some(AppEvent::InputChar('?'))
+                }
+            }
+            // Filtering only starts when input is empty
+            KeyCode::Char('/') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::StartFilter)
+                } else {
+                    Some(AppEvent::InputChar('/'))
+                }
+            }
+            // Settings only open when input is empty
+            KeyCode::Char('s') => {
+                if input_empty {
+                    Some(AppEvent::ShowSettings)
+                } else {
+                    Some(AppEvent::InputChar('s'))
+                }
+            }
+            // The browser picker only opens when input is empty
+            KeyCode::Char('b') => {
+                if input_empty {
+                    Some(AppEvent::ShowBrowserPicker)
+                } else {
+                    Some(AppEvent::InputChar('b'))
+                }
+            }
+            // Profiles are only cycled when input is empty
+            KeyCode::Char('p') => {
+                if input_empty {
+                    Some(AppEvent::CycleProfile)
+                } else {
+                    Some(AppEvent::InputChar('p'))
+                }
+            }
             // Delete job only works when input is empty and has jobs
             KeyCode::Char('d') => {
                 if input_empty && has_jobs {
@@ -145,9 +562,187 @@ impl App {
                     Some(AppEvent::InputChar('d'))
                 }
             }
-            // 'c' is just a regular character for input
-            KeyCode::Char('c') => Some(AppEvent::InputChar('c')),
+            // Editing a job only works when input is empty and has jobs
+            KeyCode::Char('e') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::EditJob)
+                } else {
+                    Some(AppEvent::InputChar('e'))
+                }
+            }
+            // Jumping between failed jobs only works when input is empty and has jobs
+            KeyCode::Char('n') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::NextFailed)
+                } else {
+                    Some(AppEvent::InputChar('n'))
+                }
+            }
+            KeyCode::Char('N') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::PrevFailed)
+                } else {
+                    Some(AppEvent::InputChar('N'))
+                }
+            }
+            KeyCode::Char('F') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::ListFormats)
+                } else {
+                    Some(AppEvent::InputChar('F'))
+                }
+            }
+            KeyCode::Char('S') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::CycleSort)
+                } else {
+                    Some(AppEvent::InputChar('S'))
+                }
+            }
+            // Log view toggle only works when input is empty and has jobs
+            KeyCode::Char('l') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::ToggleLogView)
+                } else {
+                    Some(AppEvent::InputChar('l'))
+                }
+            }
+            KeyCode::Char('v') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::ToggleCompactView)
+                } else {
+                    Some(AppEvent::InputChar('v'))
+                }
+            }
+            // Requeue at another quality only works when input is empty and has jobs
+            KeyCode::Char('R') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::RequeueJob)
+                } else {
+                    Some(AppEvent::InputChar('R'))
+                }
+            }
+            // Retrying a failed job only works when input is empty and has jobs
+            KeyCode::Char('r') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::RetryJob)
+                } else {
+                    Some(AppEvent::InputChar('r'))
+                }
+            }
+            // Clearing completed jobs only works when input is empty and has jobs
+            KeyCode::Char('x') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::ClearCompleted)
+                } else {
+                    Some(AppEvent::InputChar('x'))
+                }
+            }
+            KeyCode::Char('E') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::Export)
+                } else {
+                    Some(AppEvent::InputChar('E'))
+                }
+            }
+            // Opening the output only works when input is empty and has jobs
+            KeyCode::Char('o') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::OpenOutputFolder)
+                } else {
+                    Some(AppEvent::InputChar('o'))
+                }
+            }
+            KeyCode::Char('O') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::OpenOutputFile)
+                } else {
+                    Some(AppEvent::InputChar('O'))
+                }
+            }
+            // Copying the output path only works when input is empty and has jobs
+            KeyCode::Char('y') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::CopyPath)
+                } else {
+                    Some(AppEvent::InputChar('y'))
+                }
+            }
+            // Cancel job only works when input is empty and has jobs
+            KeyCode::Char('c') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::CancelJob)
+                } else {
+                    Some(AppEvent::InputChar('c'))
+                }
+            }
+            // Pausing the queue only works when input is empty and has jobs
+            KeyCode::Char(' ') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::TogglePause)
+                } else {
+                    Some(AppEvent::InputChar(' '))
+                }
+            }
+            // Changing concurrency only works when input is empty
+            KeyCode::Char('+') => {
+                if input_empty {
+                    Some(AppEvent::IncreaseConcurrency)
+                } else {
+                    Some(AppEvent::InputChar('+'))
+                }
+            }
+            KeyCode::Char('-') => {
+                if input_empty {
+                    Some(AppEvent::DecreaseConcurrency)
+                } else {
+                    Some(AppEvent::InputChar('-'))
+                }
+            }
             // Navigation only works when input is empty and has jobs
+            KeyCode::Char('k') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::MoveUp)
+                } else {
+                    Some(AppEvent::InputChar('k'))
+                }
+            }
+            KeyCode::Char('j') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::MoveDown)
+                } else {
+                    Some(AppEvent::InputChar('j'))
+                }
+            }
+            // Shift moves the selected queued job instead of the selection
+            KeyCode::Char('K') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::MoveJobUp)
+                } else {
+                    Some(AppEvent::InputChar('K'))
+                }
+            }
+            KeyCode::Char('J') => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::MoveJobDown)
+                } else {
+                    Some(AppEvent::InputChar('J'))
+                }
+            }
+            KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::MoveJobUp)
+                } else {
+                    None
+                }
+            }
+            KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::MoveJobDown)
+                } else {
+                    None
+                }
+            }
             KeyCode::Up => {
                 if input_empty && has_jobs {
                     Some(AppEvent::MoveUp)
@@ -162,8 +757,14 @@ impl App {
                     None
                 }
             }
-            // Enter submits URL
-            KeyCode::Enter => Some(AppEvent::SubmitUrl),
+            // Enter submits URL, or shows the selected job's details when input is empty
+            KeyCode::Enter => {
+                if input_empty && has_jobs {
+                    Some(AppEvent::ShowDetails)
+                } else {
+                    Some(AppEvent::SubmitUrl)
+                }
+            }
             // Escape clears input
             KeyCode::Esc => Some(AppEvent::ClearInput),
             // Backspace removes character
@@ -176,81 +777,806 @@ impl App {
 
     async fn handle_event(&mut self, event: AppEvent) -> Result<bool> {
         let mut state = self.state.lock().await;
+        let changes_jobs = matches!(
+            event,
+            AppEvent::SubmitUrl
+                | AppEvent::PasteSubmit(_)
+                | AppEvent::InputPaste(_)
+                | AppEvent::DeleteJob
+                | AppEvent::EditJob
+                | AppEvent::CancelJob
+                | AppEvent::ConfirmRequeue
+                | AppEvent::ConfirmFormat
+                | AppEvent::RetryJob
+                | AppEvent::ClearCompleted
+                | AppEvent::MoveJobUp
+                | AppEvent::MoveJobDown
+        );
 
         match event {
             AppEvent::Quit => {
-                return Ok(false);
+                // Running jobs would be killed, so make sure that's intended.
+                // Quitting again while they finish stops them after all
+                let active = state.active_jobs_count() > 0 && !state.finishing;
+                if active && state.overlay != Some(Overlay::ConfirmQuit) {
+                    state.overlay = Some(Overlay::ConfirmQuit);
+                } else if active && !state.config.kill_on_quit {
+                    state.overlay = None;
+                    state.finishing = true;
+                } else {
+                    Self::save_jobs(&state);
+                    return Ok(false);
+                }
             }
             AppEvent::InputChar(c) => {
-                state.input_buffer.push(c);
+                state.active_input().push(c);
             }
             AppEvent::InputBackspace => {
-                state.input_buffer.pop();
+                state.active_input().pop();
+            }
+            AppEvent::InputPaste(text) if state.overlay.is_none() && state.config.paste_submits => {
+                self.paste_and_submit(&mut state, &text);
+            }
+            AppEvent::PasteSubmit(text) => {
+                self.paste_and_submit(&mut state, &text);
             }
             AppEvent::InputPaste(text) => {
-                // Clean up the text (remove newlines, trim)
-                let clean_text = text.trim().replace('\n', "").replace('\r', "");
-                state.input_buffer.push_str(&clean_text);
+                let clean_text = clean_paste(&text);
+                if state.overlay.is_none() && !is_http_url(&clean_text) {
+                    state.set_status(Severity::Warn, "that doesn't look like a url");
+                }
+                state.active_input().push_str(&clean_text);
             }
             AppEvent::ClearInput => {
+                // With nothing left to clear, esc drops the filter
+                if state.input_buffer.is_empty() {
+                    state.filter.clear();
+                }
                 state.input_buffer.clear();
             }
+            AppEvent::StartFilter => {
+                state.overlay = Some(Overlay::Filter);
+            }
+            AppEvent::ClearFilter => {
+                state.filter.clear();
+                state.overlay = None;
+            }
             AppEvent::SubmitUrl => {
-                if !state.input_buffer.is_empty() {
-                    let url = state.input_buffer.clone();
-                    let job = Job::new(url);
-                    state.jobs.push(job);
-                    state.input_buffer.clear();
-                }
+                self.submit_input(&mut state);
             }
             AppEvent::DeleteJob => {
-                if !state.jobs.is_empty() {
-                    let index = state.selected_index;
-                    let job = &state.jobs[index];
-                    // Only allow deleting non-active jobs
-                    if !job.status.is_active() {
+                let index = state.selected_index;
+                // Only allow deleting non-active jobs
+                let id = state
+                    .jobs
+                    .get(index)
+                    .filter(|job| !job.status().is_active())
+                    .map(|job| job.id);
+                if let Some(id) = id {
+                    // The first press only marks the job, there's no undo
+                    if state.is_pending_delete(id) {
+                        // Queued jobs may already have a task waiting for a slot
+                        self.queue.cancel_job(&state.jobs[index]);
                         state.remove_job(index);
+                        state.pending_delete = None;
+                        state.status_message = None;
+                    } else {
+                        state.pending_delete = Some((id, Instant::now()));
+                        state.set_status(Severity::Warn, "press d again to delete");
                     }
                 }
             }
 
+            AppEvent::EditJob => {
+                let index = state.selected_index;
+                let input = state
+                    .jobs
+                    .get(index)
+                    .filter(|job| matches!(job.status(), JobStatus::Queued | JobStatus::Failed))
+                    .map(job_input);
+                if let Some(input) = input {
+                    // Submitting adds it again
+                    self.queue.cancel_job(&state.jobs[index]);
+                    state.remove_job(index);
+                    state.input_buffer = input;
+                }
+            }
+            AppEvent::CancelJob => {
+                let index = state.selected_index;
+                if let Some(job) = state.jobs.get_mut(index) {
+                    // Only active jobs can be cancelled
+                    if let Some(kind) = job.stages.running() {
+                        self.queue.cancel_job(job);
+                        job.stages.get_mut(kind).status = StageStatus::Cancelled;
+                        job.finished_at = Some(Instant::now());
+                    }
+                }
+            }
+            AppEvent::RetryJob => {
+                let index = state.selected_index;
+                if let Some(job) = state.jobs.get_mut(index) {
+                    // Only failed jobs can be retried, process_queue starts them
+                    // again from the stage that failed
+                    if job.status().is_failed() {
+                        job.reset_for_retry();
+                    }
+                }
+            }
+            AppEvent::ClearCompleted => {
+                state.clear_completed();
+            }
+            AppEvent::OpenOutputFolder => {
+                // Jobs without an output yet are ignored
+                if let Some(folder) = state
+                    .selected_job()
+                    .filter(|job| job.status().is_complete())
+                    .and_then(|job| job.output_path.as_deref())
+                    .and_then(|path| path.parent())
+                {
+                    open_with_system(folder);
+                }
+            }
+            AppEvent::OpenOutputFile => {
+                if let Some(path) = state
+                    .selected_job()
+                    .filter(|job| job.status().is_complete())
+                    .and_then(|job| job.output_path.as_deref())
+                {
+                    open_with_system(path);
+                }
+            }
+            AppEvent::CopyPath => {
+                if let Some(path) = state
+                    .selected_job()
+                    .filter(|job| job.status().is_complete())
+                    .and_then(|job| job.output_path.as_deref())
+                {
+                    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+                    let copied = self.clipboard.as_mut().is_some_and(|clipboard| {
+                        clipboard.set_text(path.to_string_lossy()).is_ok()
+                    });
+                    if copied {
+                        state.set_status(Severity::Info, "copied path");
+                    } else {
+                        state.set_status(Severity::Error, "couldn't copy, no clipboard available");
+                    }
+                }
+            }
+            AppEvent::ShowDetails => {
+                if let Some(job) = state.selected_job() {
+                    let preview = if state.config.thumbnail_preview && job.status().is_complete() {
+                        job.thumbnail_path
+                            .as_deref()
+                            .and_then(thumbnail::load_thumbnail)
+                    } else {
+                        None
+                    };
+                    state.preview = preview;
+                    state.overlay = Some(Overlay::Details { scroll: 0 });
+                }
+            }
+            AppEvent::CloseOverlay => {
+                if let Some(Overlay::Settings {
+                    index,
+                    editing: true,
+                }) = state.overlay
+                {
+                    // Only stop editing, discarding the typed value
+                    state.edit_buffer.clear();
+                    state.overlay = Some(Overlay::Settings {
+                        index,
+                        editing: false,
+                    });
+                } else {
+                    state.overlay = None;
+                    state.preview = None;
+                }
+            }
+
+            AppEvent::IncreaseConcurrency | AppEvent::DecreaseConcurrency => {
+                // Not written to the config file, the settings overlay changes the default
+                let current = state.config.max_concurrent_downloads;
+                let max = if matches!(event, AppEvent::IncreaseConcurrency) {
+                    (current + 1).min(MAX_CONCURRENT_DOWNLOADS)
+                } else {
+                    current.saturating_sub(1).max(1)
+                };
+                state.config.max_concurrent_downloads = max;
+                self.queue.set_max_concurrent(max);
+            }
+            AppEvent::TogglePause => {
+                state.paused = !state.paused;
+            }
+            AppEvent::ToggleHelp => {
+                state.overlay = match state.overlay {
+                    Some(Overlay::Help) => None,
+                    _ => Some(Overlay::Help),
+                };
+            }
+            AppEvent::ShowSettings => {
+                state.overlay = Some(Overlay::Settings {
+                    index: 0,
+                    editing: false,
+                });
+            }
+            AppEvent::ShowBrowserPicker => {
+                // Preselect the current browser, or else the first one found
+                state.installed_browsers = deps::installed_browsers();
+                let current = state
+                    .config
+                    .cookies_from_browser
+                    .as_deref()
+                    .or(state.installed_browsers.first().copied());
+                let index = current
+                    .and_then(|browser| COOKIE_BROWSERS.iter().position(|b| *b == browser))
+                    .map_or(0, |i| i + 1);
+                state.overlay = Some(Overlay::BrowserPicker { index });
+            }
+            AppEvent::ConfirmBrowser => {
+                if let Some(Overlay::BrowserPicker { index }) = state.overlay.take() {
+                    // For this session only, like the concurrency
+                    let browser = index.checked_sub(1).map(|i| COOKIE_BROWSERS[i].to_string());
+                    let message = match (&browser, &state.config.cookies_file) {
+                        (Some(_), Some(_)) => "cookies_file is set and used instead".to_string(),
+                        (Some(browser), None) => format!("cookies from {}", browser),
+                        (None, _) => "no browser cookies".to_string(),
+                    };
+                    state.config.cookies_from_browser = browser;
+                    self.queue.set_config(state.config.with_active_profile());
+                    state.set_status(Severity::Info, message);
+                }
+            }
+            AppEvent::CycleProfile => {
+                // Applies to jobs started from now on, like the settings
+                state.config.cycle_profile();
+                let config = state.config.with_active_profile();
+                state.selected_quality = config.default_quality.clone();
+                let profile = state.config.active_profile.clone();
+                let saved = config::update_config(|file| file.active_profile = profile);
+                self.queue.set_config(config);
+
+                match (saved, &state.config.active_profile) {
+                    (Err(e), _) => {
+                        state.set_status(Severity::Error, format!("profile not saved: {}", e))
+                    }
+                    (Ok(()), Some(name)) => {
+                        let message = format!("profile \"{}\"", name);
+                        state.set_status(Severity::Info, message)
+                    }
+                    (Ok(()), None) => state.set_status(Severity::Info, "no profile"),
+                }
+            }
+            AppEvent::EditSetting => {
+                if let Some(Overlay::Settings { index, editing }) = state.overlay {
+                    let setting = Setting::ALL[index];
+                    let mut changed = false;
+
+                    if editing {
+                        let value = state.edit_buffer.trim().to_string();
+                        // Invalid values stay in the editor to be corrected
+                        if setting.set(&mut state.config, &value) {
+                            state.edit_buffer.clear();
+                            state.overlay = Some(Overlay::Settings {
+                                index,
+                                editing: false,
+                            });
+                            changed = true;
+                        }
+                    } else if setting.is_text() {
+                        state.edit_buffer = setting.value(&state.config);
+                        state.overlay = Some(Overlay::Settings {
+                            index,
+                            editing: true,
+                        });
+                    } else {
+                        setting.cycle(&mut state.config);
+                        changed = true;
+                    }
+
+                    if changed {
+                        let config = state.config.with_active_profile();
+                        state.selected_quality = config.default_quality.clone();
+                        let saved = config::update_config(|file| setting.copy(&state.config, file));
+                        if let Err(e) = saved {
+                            state.set_status(Severity::Error, format!("setting not saved: {}", e));
+                        }
+                        self.queue.set_config(config);
+                    }
+                }
+            }
+
+            AppEvent::RequeueJob => {
+                if let Some(job) = state.selected_job() {
+                    // Only completed downloads can be requeued
+                    if job.status().is_complete() && !job.local {
+                        let quality = job
+                            .quality
+                            .clone()
+                            .unwrap_or_else(|| state.selected_quality.clone());
+                        // Preselect the next higher quality
+                        let index = QUALITY_PRESETS
+                            .iter()
+                            .position(|q| *q == quality)
+                            .unwrap_or(0)
+                            .saturating_sub(1);
+                        state.overlay = Some(Overlay::QualityPicker {
+                            index,
+                            delete_original: false,
+                        });
+                    }
+                }
+            }
+            AppEvent::ToggleDeleteOriginal => {
+                if let Some(Overlay::QualityPicker {
+                    delete_original, ..
+                }) = &mut state.overlay
+                {
+                    *delete_original = !*delete_original;
+                }
+            }
+            AppEvent::ConfirmRequeue => {
+                if let Some(Overlay::QualityPicker {
+                    index,
+                    delete_original,
+                }) = state.overlay.take()
+                {
+                    let original_index = state.selected_index;
+                    if let Some(original) = state.jobs.get(original_index) {
+                        let mut job = Job::new(original.url.clone());
+                        job.title = original.title.clone();
+                        job.audio_only = original.audio_only;
+                        job.clip_range = original.clip_range.clone();
+                        job.quality = Some(QUALITY_PRESETS[index].to_string());
+
+                        if delete_original {
+                            if let Some(path) = &original.output_path {
+                                let _ = std::fs::remove_file(path);
+                            }
+                            state.jobs[original_index] = job;
+                        } else {
+                            // Place the new job right after the original for comparison
+                            state.jobs.insert(original_index + 1, job);
+                            state.selected_index = original_index + 1;
+                        }
+                    }
+                }
+            }
+
+            AppEvent::ListFormats => {
+                if let Some(job) = state.selected_job().filter(|job| !job.local) {
+                    let (job_id, url) = (job.id, job.url.clone());
+                    let ytdlp = state.config.ytdlp_program();
+                    state.formats = None;
+                    state.overlay = Some(Overlay::Formats { index: 0 });
+
+                    let shared = self.state.clone();
+                    tokio::spawn(async move {
+                        let result = list_formats(&ytdlp, &url).await;
+                        let mut state = shared.lock().await;
+                        // Closed, or moved on to another job, in the meantime
+                        let open = matches!(state.overlay, Some(Overlay::Formats { .. }));
+                        if !open || state.selected_job().map(|job| job.id) != Some(job_id) {
+                            return;
+                        }
+                        match result {
+                            Ok(formats) => state.formats = Some(formats),
+                            Err(e) => {
+                                state.overlay = None;
+                                state.set_status(
+                                    Severity::Error,
+                                    format!("can't list formats: {}", e),
+                                );
+                            }
+                        }
+                    });
+                }
+            }
+            AppEvent::ConfirmFormat => {
+                if let Some(Overlay::Formats { index }) = state.overlay {
+                    let format = state
+                        .formats
+                        .as_ref()
+                        .and_then(|formats| formats.get(index))
+                        .map(|entry| entry.id.clone());
+                    let original_index = state.selected_index;
+                    if format.is_some() {
+                        state.overlay = None;
+                    }
+                    if let (Some(format), Some(original)) =
+                        (format, state.jobs.get_mut(original_index))
+                    {
+                        if original.stages.download.status == StageStatus::Pending {
+                            // Not started yet, so it can still be changed
+                            original.format = Some(format);
+                        } else {
+                            // Place the new job right after the original, like requeueing
+                            let mut job = Job::new(original.url.clone());
+                            job.title = original.title.clone();
+                            job.audio_only = original.audio_only;
+                            job.clip_range = original.clip_range.clone();
+                            job.format = Some(format);
+                            state.jobs.insert(original_index + 1, job);
+                            state.selected_index = original_index + 1;
+                        }
+                    }
+                }
+            }
+            AppEvent::ToggleLogView => {
+                state.list_mode = match state.list_mode {
+                    ListMode::Log => ListMode::Detailed,
+                    _ => ListMode::Log,
+                };
+            }
+            AppEvent::ToggleCompactView => {
+                state.list_mode = match state.list_mode {
+                    ListMode::Compact => ListMode::Detailed,
+                    _ => ListMode::Compact,
+                };
+            }
+            AppEvent::CycleSort => {
+                // The selected job stays selected, wherever it moves to
+                state.sort_mode = state.sort_mode.next();
+                let message = format!("sorted by {}", state.sort_mode.label());
+                state.set_status(Severity::Info, message);
+            }
+            AppEvent::NextFailed => {
+                if let Some(index) = state.find_failed_job(true) {
+                    state.selected_index = index;
+                }
+            }
+            AppEvent::PrevFailed => {
+                if let Some(index) = state.find_failed_job(false) {
+                    state.selected_index = index;
+                }
+            }
+
             AppEvent::MoveUp => {
-                if state.selected_index > 0 {
-                    state.selected_index -= 1;
+                if let Some(
+                    Overlay::QualityPicker { index, .. }
+                    | Overlay::Settings { index, .. }
+                    | Overlay::Formats { index }
+                    | Overlay::BrowserPicker { index },
+                ) = &mut state.overlay
+                {
+                    *index = index.saturating_sub(1);
+                } else if let Some(Overlay::Details { scroll }) = &mut state.overlay {
+                    *scroll = scroll.saturating_sub(1);
+                } else {
+                    state.move_selection(true);
                 }
             }
+            AppEvent::ClickRow(row) => {
+                if let Some(&(_, _, index)) = state
+                    .job_rows
+                    .iter()
+                    .find(|(y, height, _)| (*y..y + height).contains(&row))
+                {
+                    state.selected_index = index;
+                }
+            }
+            AppEvent::Resize => self.redraw = true,
+            AppEvent::Export => {
+                let dir = PathBuf::from(&state.config.output_directory);
+                match export::export_jobs(&state.jobs, &dir, &state.config.export_format) {
+                    Ok(path) => {
+                        let message =
+                            format!("exported {} jobs to {}", state.jobs.len(), path.display());
+                        state.set_status(Severity::Info, message);
+                    }
+                    Err(e) => state.set_status(Severity::Error, format!("export failed: {}", e)),
+                }
+            }
+            AppEvent::MoveJobUp => state.move_queued_job(true),
+            AppEvent::MoveJobDown => state.move_queued_job(false),
             AppEvent::MoveDown => {
-                if state.selected_index < state.jobs.len().saturating_sub(1) {
-                    state.selected_index += 1;
+                let format_count = state.formats.as_ref().map_or(0, Vec::len);
+                if let Some(Overlay::QualityPicker { index, .. }) = &mut state.overlay {
+                    *index = (*index + 1).min(QUALITY_PRESETS.len() - 1);
+                } else if let Some(Overlay::Settings { index, .. }) = &mut state.overlay {
+                    *index = (*index + 1).min(Setting::ALL.len() - 1);
+                } else if let Some(Overlay::Formats { index }) = &mut state.overlay {
+                    *index = (*index + 1).min(format_count.saturating_sub(1));
+                } else if let Some(Overlay::BrowserPicker { index }) = &mut state.overlay {
+                    *index = (*index + 1).min(COOKIE_BROWSERS.len());
+                } else if let Some(Overlay::Details { scroll }) = &mut state.overlay {
+                    // Limited to the length of the details when rendering
+                    *scroll = scroll.saturating_add(1);
+                } else {
+                    state.move_selection(false);
                 }
             }
         }
 
+        // Typing the filter or changing jobs may hide the selected job
+        state.select_visible();
+
+        if changes_jobs {
+            Self::save_jobs(&state);
+        }
+
         Ok(true)
     }
 
+    /// Look for a newer yt-dlp in the background, if enabled. An outdated
+    /// yt-dlp is the most common reason for failing downloads.
+    async fn spawn_update_check(&self) {
+        let state = self.state.clone();
+        {
+            let state = state.lock().await;
+            if !state.config.check_ytdlp_updates || state.dependencies.ytdlp.is_none() {
+                return;
+            }
+        }
+
+        tokio::spawn(async move {
+            let Some(latest) = deps::latest_ytdlp_version().await else {
+                return;
+            };
+            let mut state = state.lock().await;
+            state.dependencies.ytdlp_latest = Some(latest);
+            if let Some(latest) = state.dependencies.ytdlp_update().map(str::to_string) {
+                state.set_status(
+                    Severity::Info,
+                    format!("yt-dlp {} is available, run yt-dlp -U", latest),
+                );
+            }
+        });
+    }
+
+    /// Create a job for a url, resolving its title in the background or, if
+    /// allowed, expanding it into its playlist entries
+    fn url_job(&self, state: &AppState, url: String, fetch_title: bool) -> Job {
+        let mut job = Job::new(url.clone());
+        job.audio_only = state.config.audio_only;
+        job.simulate = state.config.dry_run;
+
+        let job_id = job.id;
+        let update_tx = self.job_update_tx.clone();
+        let ytdlp = state.config.ytdlp_program();
+        if state.config.allow_playlists {
+            // Probing the playlist also resolves the title of single videos
+            job.expanding_playlist = true;
+            tokio::spawn(async move {
+                // A failed probe leaves the job to the real download
+                let entries = expand_playlist(&ytdlp, &url).await.unwrap_or_default();
+                let _ = update_tx.send((job_id, JobUpdate::PlaylistEntries(entries)));
+            });
+        } else if fetch_title {
+            // Show the title while the job is still queued. Failures are left
+            // to the real download attempt to report.
+            tokio::spawn(async move {
+                if let Ok(title) = get_video_info(&ytdlp, &url).await {
+                    let _ = update_tx.send((job_id, JobUpdate::Title(title)));
+                }
+            });
+        }
+        job
+    }
+
+    /// Add a job for what's typed in the input box: a url, optionally with a
+    /// format, quality or clip, a local file or "@" and a file of urls
+    fn submit_input(&self, state: &mut AppState) {
+        // Files dragged into the terminal are often pasted with quotes
+        let path = PathBuf::from(state.input_buffer.trim().trim_matches(['\'', '"']));
+        let list_path = state
+            .input_buffer
+            .trim()
+            .strip_prefix('@')
+            .map(str::to_string);
+        if let Some(list_path) = list_path {
+            // A file with one url per line, e.g. "@~/urls.txt"
+            match std::fs::read_to_string(expand_home(&list_path)) {
+                Ok(contents) => {
+                    let message = self.add_url_list(state, &contents);
+                    state.set_status(Severity::Info, message);
+                    state.input_buffer.clear();
+                }
+                Err(e) => {
+                    state.set_status(Severity::Error, format!("can't read {}: {}", list_path, e))
+                }
+            }
+        } else if !state.input_buffer.is_empty() && path.is_file() {
+            state.jobs.push(Job::from_local_file(path));
+            state.input_buffer.clear();
+        } else if !state.input_buffer.is_empty() {
+            // "sim:" for a dry run and an optional format or quality may
+            // come first, e.g. "fmt:137+140 https://..." or "720p https://..."
+            let (simulate, rest) = split_simulate_prefix(state.input_buffer.trim());
+            let (format, rest) = split_format_prefix(rest);
+            let (quality, rest) = split_quality_prefix(rest);
+            // And an optional clip at the end, e.g. "https://... @ 1:10-1:40"
+            let (url, clip_range) = match split_clip_range(rest) {
+                Ok(split) => split,
+                Err(message) => {
+                    // Keep the input so the range can be corrected
+                    state.set_status(Severity::Error, message);
+                    return;
+                }
+            };
+            let url = url.to_string();
+            // Two jobs for the same video would write the same file
+            if !simulate && !state.config.allow_duplicates && state.has_duplicate(&url, &clip_range)
+            {
+                state.set_status(Severity::Warn, "already queued");
+                return;
+            }
+            // Only warn, yt-dlp may still know what to do with it
+            if !is_http_url(&url) {
+                state.set_status(
+                    Severity::Warn,
+                    format!("\"{}\" doesn't look like a url", url),
+                );
+            }
+            let mut job = self.url_job(state, url, true);
+            job.quality = quality;
+            job.format = format;
+            job.clip_range = clip_range;
+            job.simulate |= simulate;
+
+            state.jobs.push(job);
+            state.input_buffer.clear();
+        }
+    }
+
+    /// Paste the clipboard and submit it right away. Every line of a
+    /// multi-line paste becomes a job of its own.
+    fn paste_and_submit(&self, state: &mut AppState, text: &str) {
+        if text.lines().filter(|line| !line.trim().is_empty()).count() > 1 {
+            let message = self.add_url_list(state, text);
+            state.set_status(Severity::Info, message);
+        } else {
+            state.input_buffer.push_str(&clean_paste(text));
+            self.submit_input(state);
+        }
+    }
+
+    /// Add a job for each line of a url list, returning a message with how
+    /// many were added, and how many lines were skipped as invalid or as
+    /// duplicates. Blank lines and comments starting with "#" are ignored.
+    /// Lines take the same quality and clip syntax as the input box.
+    fn add_url_list(&self, state: &mut AppState, contents: &str) -> String {
+        let (mut added, mut skipped, mut duplicates) = (0, 0, 0);
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (simulate, rest) = split_simulate_prefix(line);
+            let (format, rest) = split_format_prefix(rest);
+            let (quality, rest) = split_quality_prefix(rest);
+            match split_clip_range(rest) {
+                Ok((url, clip_range))
+                    if !simulate
+                        && !state.config.allow_duplicates
+                        && state.has_duplicate(url, &clip_range) =>
+                {
+                    duplicates += 1;
+                }
+                Ok((url, clip_range)) if is_http_url(url) => {
+                    // Titles arrive once each download starts, rather than
+                    // probing hundreds of urls at once
+                    let mut job = self.url_job(state, url.to_string(), false);
+                    job.quality = quality;
+                    job.format = format.clone();
+                    job.clip_range = clip_range;
+                    job.simulate |= simulate;
+                    state.jobs.push(job);
+                    added += 1;
+                }
+                _ => skipped += 1,
+            }
+        }
+
+        let mut message = format!("added {} jobs", added);
+        if skipped > 0 {
+            message.push_str(&format!(", skipped {} invalid lines", skipped));
+        }
+        if duplicates > 0 {
+            message.push_str(&format!(", {} already queued", duplicates));
+        }
+        message
+    }
+
+    /// Persist the job list so it survives restarts. Failing to do so must
+    /// not interrupt the session.
+    fn save_jobs(state: &AppState) {
+        let _ = config::save_jobs(&state.jobs);
+    }
+
     async fn apply_job_update(&mut self, job_id: uuid::Uuid, update: JobUpdate) {
         let mut state = self.state.lock().await;
 
+        if let JobUpdate::PlaylistEntries(entries) = update {
+            state.expand_playlist_job(job_id, entries);
+            Self::save_jobs(&state);
+            return;
+        }
+        if let JobUpdate::Downloaded(bytes, time) = update {
+            state.session_stats.bytes += bytes;
+            state.session_stats.download_time += time;
+            state.lifetime_stats.bytes += bytes;
+            let _ = config::save_stats(&state.lifetime_stats);
+            return;
+        }
+        if let JobUpdate::Chapters(chapters) = update {
+            let config = state.config.with_active_profile();
+            let Some(job) = state.get_job_by_id(job_id) else {
+                return;
+            };
+            // Converted like the full video would be, and saved next to it
+            let convert = config.auto_convert && !job.audio_only;
+            let quality = job
+                .quality
+                .clone()
+                .unwrap_or_else(|| state.selected_quality.clone());
+            let output_dir = config.output_dir_for(&job.url, &quality);
+            let message = format!("split into {} chapters", chapters.len());
+            state.add_chapter_jobs(job_id, chapters, convert, &output_dir);
+            state.set_status(Severity::Info, message);
+            Self::save_jobs(&state);
+            return;
+        }
+
+        let changes_status = matches!(update, JobUpdate::Stage(..));
+        let mut finished_job = None;
+
         if let Some(job) = state.get_job_by_id_mut(job_id) {
+            // Anything yt-dlp reports shows the download is still going
+            if matches!(
+                update,
+                JobUpdate::Stage(StageKind::Download, StageStatus::Running)
+                    | JobUpdate::Progress(_)
+                    | JobUpdate::Speed(_)
+                    | JobUpdate::Eta(_)
+                    | JobUpdate::Size(_)
+                    | JobUpdate::Postprocess(_)
+                    | JobUpdate::Retrying(_)
+            ) {
+                job.last_progress_at = Some(Instant::now());
+            }
             match update {
-                JobUpdate::Status(status) => {
-                    job.status = status;
+                JobUpdate::Stage(kind, status) => {
+                    // Updates sent right before cancelling must not revive the job
+                    let previous = job.status();
+                    if previous != JobStatus::Cancelled {
+                        job.stages.get_mut(kind).status = status;
+                    }
+                    if kind == StageKind::Download {
+                        job.postprocess = None;
+                    }
+
+                    let current = job.status();
+                    if current.is_active() && job.started_at.is_none() {
+                        job.started_at = Some(Instant::now());
+                    }
+                    if current != previous && (current.is_complete() || current.is_failed()) {
+                        job.finished_at = Some(Instant::now());
+                        finished_job = Some(job.clone());
+                    }
                 }
                 JobUpdate::Progress(progress) => {
+                    // yt-dlp downloads the audio after the video, or retries
+                    job.postprocess = None;
                     job.progress = progress;
                 }
+                JobUpdate::Postprocess(step) => {
+                    job.postprocess = Some(step);
+                }
                 JobUpdate::Speed(speed) => {
                     job.speed = Some(speed);
                 }
                 JobUpdate::Eta(eta) => {
                     job.eta = Some(eta);
                 }
+                JobUpdate::Size(size) => {
+                    job.size = Some(size);
+                }
                 JobUpdate::Title(title) => {
                     job.title = Some(title);
                 }
-                JobUpdate::Error(error) => {
-                    job.error = Some(error);
+                JobUpdate::Error(kind, error) => {
+                    tracing::error!(job = %job_id, stage = ?kind, "{}", error);
+                    job.stages.get_mut(kind).error = Some(error);
                 }
                 JobUpdate::TempPath(path) => {
                     job.temp_path = Some(path);
@@ -258,6 +1584,65 @@ impl App {
                 JobUpdate::OutputPath(path) => {
                     job.output_path = Some(path);
                 }
+                JobUpdate::ThumbnailPath(path) => {
+                    job.thumbnail_path = Some(path);
+                }
+                JobUpdate::Duration(duration) => {
+                    job.duration = Some(duration);
+                }
+                JobUpdate::Height(height) => {
+                    job.height = Some(height);
+                }
+                JobUpdate::OutputUnavailable(dir) => {
+                    job.output_unavailable = Some(dir);
+                }
+                JobUpdate::Retrying(retry) => {
+                    job.retries = retry;
+                    job.postprocess = None;
+                    job.progress = 0.0;
+                    job.speed = None;
+                    job.eta = None;
+                    job.size = None;
+                }
+                // Handled above, as they add or replace jobs or touch no job
+                JobUpdate::PlaylistEntries(_)
+                | JobUpdate::Chapters(_)
+                | JobUpdate::Downloaded(..) => {}
+            }
+        }
+
+        if changes_status {
+            Self::save_jobs(&state);
+            // A "status:" filter may no longer match the selected job
+            state.select_visible();
+        }
+
+        if let Some(job) = finished_job {
+            tracing::info!(
+                job = %job.id,
+                status = job.status().label(),
+                output = ?job.output_path,
+                "finished job"
+            );
+            if state.config.notifications {
+                notify::notify_job_finished(&job);
+            }
+            let status = job.status();
+            if (status.is_complete() && state.config.bell_on_complete)
+                || (status.is_failed() && state.config.bell_on_error)
+            {
+                ring_bell();
+            }
+            if let Some(webhook_url) = &state.config.webhook_url {
+                webhook::post_job_finished(webhook_url, &job);
+            }
+            // The history and stats are a convenience, failing to write them
+            // is ignored
+            if job.status().is_complete() && !job.simulate {
+                let _ = history::append_history(&job);
+                state.session_stats.completed += 1;
+                state.lifetime_stats.completed += 1;
+                let _ = config::save_stats(&state.lifetime_stats);
             }
         }
     }
@@ -265,20 +1650,293 @@ impl App {
     async fn process_queue(&mut self) {
         let state = self.state.lock().await;
 
+        // Restored jobs would only fail without the tools to run them
+        if state.missing_dependencies() || state.paused || state.finishing {
+            return;
+        }
+
         // Find queued jobs
         let queued_jobs: Vec<_> = state
             .jobs
             .iter()
-            .filter(|j| j.status == JobStatus::Queued)
-            .map(|j| (j.id, j.url.clone()))
+            .filter(|j| j.status() == JobStatus::Queued && !j.expanding_playlist)
+            .map(|j| {
+                let quality = j
+                    .quality
+                    .clone()
+                    .unwrap_or_else(|| state.selected_quality.clone());
+                (j.clone(), quality)
+            })
             .collect();
 
-        let quality = state.selected_quality.clone();
         drop(state);
 
-        // Start queued jobs
-        for (job_id, url) in queued_jobs {
-            self.queue.start_job(job_id, url, quality.clone());
+        // Start queued jobs, resuming at conversion if the download already finished
+        for (job, quality) in queued_jobs {
+            match (job.stages.download.status.is_done(), &job.temp_path) {
+                (true, Some(temp_path)) => {
+                    self.queue
+                        .start_conversion(&job, temp_path.clone(), &quality);
+                }
+                _ => self.queue.start_job(&job, quality),
+            }
+        }
+    }
+}
+
+/// Terminal title showing what carbon is up to, e.g. "carbon — 2 active, 5 queued"
+fn window_title(state: &AppState) -> String {
+    let counts: Vec<String> = [
+        (state.active_jobs_count(), "active"),
+        (state.queued_jobs_count(), "queued"),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, label)| format!("{} {}", count, label))
+    .collect();
+
+    if counts.is_empty() {
+        "carbon".to_string()
+    } else {
+        format!("carbon — {}", counts.join(", "))
+    }
+}
+
+/// Ring the terminal bell, written past ratatui's buffer
+fn ring_bell() {
+    let _ = crossterm::execute!(std::io::stdout(), crossterm::style::Print("\x07"));
+}
+
+/// Carry the settings changed with a key this session over to a reloaded
+/// `config`: the concurrency, the cookie browser and the profile. `read` is
+/// the config as previously read from the file
+fn keep_session_settings(read: &Config, session: &Config, config: &mut Config) {
+    if session.max_concurrent_downloads != read.max_concurrent_downloads {
+        config.max_concurrent_downloads = session.max_concurrent_downloads;
+    }
+    if session.cookies_from_browser != read.cookies_from_browser {
+        config.cookies_from_browser = session.cookies_from_browser.clone();
+    }
+    let profile_defined = session
+        .active_profile
+        .as_ref()
+        .is_none_or(|name| config.profiles.contains_key(name));
+    if session.active_profile != read.active_profile && profile_defined {
+        config.active_profile = session.active_profile.clone();
+    }
+}
+
+/// A pasted url without the surrounding whitespace and line breaks that
+/// often come with it
+fn clean_paste(text: &str) -> String {
+    text.trim().replace(['\n', '\r'], "")
+}
+
+/// Split a leading "sim:" off the input, asking for a dry run. The url may
+/// follow right away, as in "sim:https://..."
+fn split_simulate_prefix(input: &str) -> (bool, &str) {
+    match input.strip_prefix("sim:") {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, input),
+    }
+}
+
+/// Split a leading "fmt:" format code like "fmt:137+140" off the input
+fn split_format_prefix(input: &str) -> (Option<String>, &str) {
+    match input.split_once(char::is_whitespace) {
+        Some((first, rest)) => match first.strip_prefix("fmt:") {
+            Some(format) if !format.is_empty() => (Some(format.to_string()), rest.trim_start()),
+            _ => (None, input),
+        },
+        None => (None, input),
+    }
+}
+
+/// The input that submits a job like this one again, with its dry run,
+/// format or quality and clip
+fn job_input(job: &Job) -> String {
+    let mut input = if job.simulate {
+        "sim: ".to_string()
+    } else {
+        String::new()
+    };
+    match (&job.format, &job.quality) {
+        (Some(format), _) => input.push_str(&format!("fmt:{} ", format)),
+        (None, Some(quality)) => input.push_str(&format!("{} ", quality)),
+        (None, None) => {}
+    }
+    input.push_str(&job.url);
+    if let Some((start, end)) = &job.clip_range {
+        input.push_str(&format!(" @ {}-{}", start, end));
+    }
+    input
+}
+
+/// Split a leading quality like "720p" or "best" off the input. Jobs without
+/// one use the globally selected quality.
+fn split_quality_prefix(input: &str) -> (Option<String>, &str) {
+    if let Some((first, rest)) = input.split_once(char::is_whitespace) {
+        let is_height = first
+            .strip_suffix('p')
+            .is_some_and(|height| height.parse::<u32>().is_ok());
+        if is_height || QUALITY_PRESETS.contains(&first) {
+            return (Some(first.to_string()), rest.trim_start());
+        }
+    }
+    (None, input)
+}
+
+/// Split a trailing clip range like "@ 00:01:10-00:01:40" off the input.
+/// Fails if the range is there but isn't valid.
+fn split_clip_range(input: &str) -> std::result::Result<(&str, Option<ClipRange>), String> {
+    // URLs can contain "@" themselves, the range is separated by whitespace
+    let Some((url, range)) = input.rsplit_once(" @") else {
+        return Ok((input, None));
+    };
+
+    let range = range.trim();
+    let (start, end) = range
+        .split_once('-')
+        .ok_or_else(|| format!("clip \"{}\" should look like start-end", range))?;
+    let (start, end) = (start.trim(), end.trim());
+
+    match (parse_timestamp(start), parse_timestamp(end)) {
+        (Some(from), Some(to)) if from < to => {
+            Ok((url.trim_end(), Some((start.to_string(), end.to_string()))))
+        }
+        (Some(_), Some(_)) => Err(format!("clip end {} is not after its start {}", end, start)),
+        _ => Err(format!(
+            "clip \"{}\" should use HH:MM:SS or seconds, e.g. 1:10-1:40",
+            range
+        )),
+    }
+}
+
+/// Parse a timestamp in seconds, or as HH:MM:SS / MM:SS, into seconds
+fn parse_timestamp(timestamp: &str) -> Option<f64> {
+    let parts: Vec<&str> = timestamp.split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    let mut seconds = 0.0;
+    for (i, part) in parts.iter().enumerate() {
+        // Only seconds, the last part, may have a fraction
+        let seconds_part = i == parts.len() - 1;
+        let valid = |c: char| c.is_ascii_digit() || (seconds_part && c == '.');
+        if part.is_empty() || !part.chars().all(valid) {
+            return None;
         }
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(seconds)
+}
+
+/// Expand a leading "~" to the home directory, as the shell would
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Open a file or folder with the platform's default application, without
+/// waiting for it. Failures are ignored, there's nothing useful to do about them.
+fn open_with_system(path: &Path) {
+    let program = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+
+    // tokio reaps the process once it exits, even though it's never awaited
+    let _ = tokio::process::Command::new(program)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_timestamp_formats() {
+        assert_eq!(parse_timestamp("1:02:03"), Some(3723.0));
+        assert_eq!(parse_timestamp("01:10"), Some(70.0));
+        assert_eq!(parse_timestamp("90"), Some(90.0));
+        assert_eq!(parse_timestamp("1:10.5"), Some(70.5));
+        assert_eq!(parse_timestamp("12.25"), Some(12.25));
+    }
+
+    #[test]
+    fn parse_timestamp_rejects_invalid() {
+        assert_eq!(parse_timestamp(""), None);
+        assert_eq!(parse_timestamp("1:2:3:4"), None);
+        assert_eq!(parse_timestamp("1::30"), None);
+        assert_eq!(parse_timestamp("1m30"), None);
+        assert_eq!(parse_timestamp("-5"), None);
+        // Only seconds may have a fraction
+        assert_eq!(parse_timestamp("1.5:30"), None);
+        assert_eq!(parse_timestamp("1:2.5:30"), None);
+    }
+
+    #[test]
+    fn split_clip_range_without_range() {
+        let url = "https://example.com/watch?v=abc";
+        assert_eq!(split_clip_range(url), Ok((url, None)));
+    }
+
+    #[test]
+    fn split_clip_range_with_range() {
+        assert_eq!(
+            split_clip_range("https://example.com/v @ 00:01:10-00:01:40"),
+            Ok((
+                "https://example.com/v",
+                Some(("00:01:10".to_string(), "00:01:40".to_string()))
+            ))
+        );
+        assert_eq!(
+            split_clip_range("https://example.com/v @ 1:10 - 1:40"),
+            Ok((
+                "https://example.com/v",
+                Some(("1:10".to_string(), "1:40".to_string()))
+            ))
+        );
+        assert_eq!(
+            split_clip_range("https://example.com/v @ 70-100"),
+            Ok((
+                "https://example.com/v",
+                Some(("70".to_string(), "100".to_string()))
+            ))
+        );
+    }
+
+    #[test]
+    fn split_clip_range_url_with_at() {
+        let url = "https://user@example.com/@channel/video";
+        assert_eq!(split_clip_range(url), Ok((url, None)));
+        assert_eq!(
+            split_clip_range("https://example.com/@channel/video @ 0:10-0:20"),
+            Ok((
+                "https://example.com/@channel/video",
+                Some(("0:10".to_string(), "0:20".to_string()))
+            ))
+        );
+    }
+
+    #[test]
+    fn split_clip_range_end_not_after_start() {
+        assert!(split_clip_range("https://example.com/v @ 1:40-1:10").is_err());
+        assert!(split_clip_range("https://example.com/v @ 1:10-1:10").is_err());
+    }
+
+    #[test]
+    fn split_clip_range_invalid() {
+        assert!(split_clip_range("https://example.com/v @ 1:10").is_err());
+        assert!(split_clip_range("https://example.com/v @ 1.5:30-2:00").is_err());
     }
 }
@@ -1,7 +1,72 @@
-use crate::models::Config;
+use crate::converter::parse_resolution;
+use crate::downloader::is_http_url;
+use crate::models::{
+    Config, DownloadStats, Job, JobStatus, MAX_CONCURRENT_DOWNLOADS, QUALITY_PRESETS,
+};
+use crate::theme::parse_hex;
 use color_eyre::Result;
+use regex::Regex;
 use std::fs;
-use std::path::PathBuf;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::level_filters::LevelFilter;
+
+/// Presets accepted by ffmpeg's libx264 encoder
+const X264_PRESETS: [&str; 10] = [
+    "ultrafast",
+    "superfast",
+    "veryfast",
+    "faster",
+    "fast",
+    "medium",
+    "slow",
+    "slower",
+    "veryslow",
+    "placebo",
+];
+
+/// Hardware encoder families that can replace x264
+const HW_ACCELS: [&str; 3] = ["nvenc", "videotoolbox", "qsv"];
+
+/// Bounds of tick_rate_ms and poll_rate_ms
+const MIN_RATE_MS: u64 = 10;
+const MAX_RATE_MS: u64 = 1000;
+
+/// Bounds of max_filename_length
+const MIN_FILENAME_LENGTH: usize = 20;
+const MAX_FILENAME_LENGTH: usize = 240;
+
+/// Bounds of progress_bar_width
+const MIN_PROGRESS_BAR_WIDTH: usize = 5;
+const MAX_PROGRESS_BAR_WIDTH: usize = 100;
+
+/// Shortest stall timeout, longer than the longest wait between retries
+const MIN_STALL_TIMEOUT_SECS: u64 = 90;
+
+/// File formats the job list can be exported as
+const EXPORT_FORMATS: [&str; 2] = ["json", "csv"];
+
+/// Downloaders yt-dlp can hand downloads to with --downloader
+const EXTERNAL_DOWNLOADERS: [&str; 8] = [
+    "native", "aria2c", "axel", "curl", "ffmpeg", "httpie", "wget", "avconv",
+];
+
+/// SponsorBlock categories yt-dlp can remove, "poi_highlight" and "chapter"
+/// can only be marked
+const SPONSORBLOCK_CATEGORIES: [&str; 11] = [
+    "sponsor",
+    "intro",
+    "outro",
+    "selfpromo",
+    "preview",
+    "filler",
+    "interaction",
+    "music_offtopic",
+    "hook",
+    "all",
+    "default",
+];
 
 pub fn config_path() -> Result<PathBuf> {
     let config_dir = dirs::config_dir()
@@ -12,28 +77,650 @@ pub fn config_path() -> Result<PathBuf> {
     Ok(config_dir.join("config.toml"))
 }
 
+pub fn jobs_path() -> Result<PathBuf> {
+    Ok(config_path()?.with_file_name("jobs.json"))
+}
+
+pub fn stats_path() -> Result<PathBuf> {
+    Ok(config_path()?.with_file_name("stats.toml"))
+}
+
 pub fn load_config() -> Result<Config> {
-    let path = config_path()?;
+    // Create default config
+    if !config_path()?.exists() {
+        save_config(&Config::default())?;
+    }
+    let (mut config, mut warnings) = read_config()?;
+    // Only checked at startup, a reload keeps the directory of an unplugged
+    // drive so its jobs resume once it's back
+    validate_output_directory(&mut config, &mut warnings);
+    // Printed before the terminal is taken over by the UI
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+
+    // Ensure output directory exists
+    fs::create_dir_all(&config.output_directory)?;
+
+    Ok(config)
+}
 
-    if path.exists() {
-        let contents = fs::read_to_string(&path)?;
-        let config: Config = toml::from_str(&contents)?;
+/// Read the config file, with the environment overrides applied and invalid
+/// settings replaced. Also returns a warning for each replaced setting
+pub fn read_config() -> Result<(Config, Vec<String>)> {
+    let contents = fs::read_to_string(config_path()?)?;
+    let mut config: Config = toml::from_str(&contents)?;
+    let mut warnings = Vec::new();
+    apply_env_overrides(&mut config, &mut warnings);
+    warnings.extend(config.validate());
+    Ok((config, warnings))
+}
 
-        // Ensure output directory exists
-        fs::create_dir_all(&config.output_directory)?;
+/// When the config file was last changed
+pub fn config_modified() -> Option<SystemTime> {
+    fs::metadata(config_path().ok()?).ok()?.modified().ok()
+}
 
-        Ok(config)
-    } else {
-        // Create default config
-        let config = Config::default();
-        save_config(&config)?;
-        Ok(config)
+impl Config {
+    /// Replace invalid settings with their defaults, returning a warning
+    /// naming each one. This only warns, as a typo in the config shouldn't
+    /// keep the app from starting. The replacements are never saved, see
+    /// `update_config`
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        validate_concurrency(self, &mut warnings);
+        validate_rates(self, &mut warnings);
+        validate_progress_bar_width(self, &mut warnings);
+        validate_quality(self, &mut warnings);
+        validate_ffmpeg_settings(self, &mut warnings);
+        validate_target_resolution(self, &mut warnings);
+        validate_target_fps(self, &mut warnings);
+        validate_rate_limit(self, &mut warnings);
+        validate_audio_quality(self, &mut warnings);
+        validate_output_template(self, &mut warnings);
+        validate_format_fallbacks(self, &mut warnings);
+        validate_max_filename_length(self, &mut warnings);
+        validate_cookies_file(self, &mut warnings);
+        validate_tool_paths(self, &mut warnings);
+        validate_sponsorblock(self, &mut warnings);
+        validate_downloader(self, &mut warnings);
+        validate_active_profile(self, &mut warnings);
+        validate_theme(self, &mut warnings);
+        validate_webhook_url(self, &mut warnings);
+        validate_network(self, &mut warnings);
+        validate_timeouts(self, &mut warnings);
+        validate_export_format(self, &mut warnings);
+        validate_log_level(self, &mut warnings);
+        warnings
     }
 }
 
+/// Whether carbon can create files in `dir`, creating it if needed
+pub fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(".carbon-write-test");
+    let writable = fs::create_dir_all(dir).is_ok() && fs::write(&probe, b"").is_ok();
+    let _ = fs::remove_file(probe);
+    writable
+}
+
+/// Whether jobs can be saved in `dir` right now. The output directory
+/// `base` is created at startup, so when it's gone its drive is most likely
+/// unplugged. Directories below it are created as needed.
+pub fn output_available(base: &Path, dir: &Path) -> bool {
+    base.is_dir() && is_writable(dir)
+}
+
+/// Whether yt-dlp can be asked for a quality: a preset, or any height like
+/// "1440p"
+fn is_known_quality(quality: &str) -> bool {
+    QUALITY_PRESETS.contains(&quality)
+        || quality
+            .strip_suffix('p')
+            .is_some_and(|height| height.parse::<u32>().is_ok_and(|h| h > 0))
+}
+
+/// Override settings from CARBON_* environment variables, for scripts and
+/// containers. Invalid values are ignored with a warning.
+fn apply_env_overrides(config: &mut Config, warnings: &mut Vec<String>) {
+    let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+    if let Some(dir) = var("CARBON_OUTPUT_DIR") {
+        config.output_directory = dir.trim().to_string();
+    }
+
+    if let Some(value) = var("CARBON_MAX_CONCURRENT") {
+        match value.trim().parse::<usize>() {
+            Ok(max @ 1..=MAX_CONCURRENT_DOWNLOADS) => config.max_concurrent_downloads = max,
+            _ => warnings.push(format!(
+                "CARBON_MAX_CONCURRENT \"{}\" is not a number from 1 to {}, ignoring it",
+                value, MAX_CONCURRENT_DOWNLOADS
+            )),
+        }
+    }
+
+    if let Some(value) = var("CARBON_QUALITY") {
+        if is_known_quality(value.trim()) {
+            config.default_quality = value.trim().to_string();
+        } else {
+            warnings.push(format!(
+                "CARBON_QUALITY \"{}\" is not one of {} or a height like \"1440p\", ignoring it",
+                value,
+                QUALITY_PRESETS.join(", ")
+            ));
+        }
+    }
+
+    if let Some(value) = var("CARBON_AUTO_CONVERT") {
+        match value.trim().to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => config.auto_convert = true,
+            "0" | "false" | "no" | "off" => config.auto_convert = false,
+            _ => warnings.push(format!(
+                "CARBON_AUTO_CONVERT \"{}\" is not true or false, ignoring it",
+                value
+            )),
+        }
+    }
+}
+
+/// Running no jobs at all would leave everything queued forever
+fn validate_concurrency(config: &mut Config, warnings: &mut Vec<String>) {
+    if !(1..=MAX_CONCURRENT_DOWNLOADS).contains(&config.max_concurrent_downloads) {
+        let default = Config::default().max_concurrent_downloads;
+        warnings.push(format!(
+            "max_concurrent_downloads {} is outside 1-{}, using {}",
+            config.max_concurrent_downloads, MAX_CONCURRENT_DOWNLOADS, default
+        ));
+        config.max_concurrent_downloads = default;
+    }
+}
+
+/// Keep the tick and poll rates where the UI stays usable and the CPU isn't
+/// kept busy
+fn validate_rates(config: &mut Config, warnings: &mut Vec<String>) {
+    let default = Config::default();
+    for (name, rate, default) in [
+        (
+            "tick_rate_ms",
+            &mut config.tick_rate_ms,
+            default.tick_rate_ms,
+        ),
+        (
+            "poll_rate_ms",
+            &mut config.poll_rate_ms,
+            default.poll_rate_ms,
+        ),
+    ] {
+        if !(MIN_RATE_MS..=MAX_RATE_MS).contains(rate) {
+            warnings.push(format!(
+                "{} {} is outside {}-{}, using {}",
+                name, rate, MIN_RATE_MS, MAX_RATE_MS, default
+            ));
+            *rate = default;
+        }
+    }
+}
+
+fn validate_progress_bar_width(config: &mut Config, warnings: &mut Vec<String>) {
+    let width = config.progress_bar_width;
+    if !(MIN_PROGRESS_BAR_WIDTH..=MAX_PROGRESS_BAR_WIDTH).contains(&width) {
+        let default = Config::default().progress_bar_width;
+        warnings.push(format!(
+            "progress_bar_width {} is outside {}-{}, using {}",
+            width, MIN_PROGRESS_BAR_WIDTH, MAX_PROGRESS_BAR_WIDTH, default
+        ));
+        config.progress_bar_width = default;
+    }
+}
+
+/// Unknown qualities would silently download the best format instead
+fn validate_quality(config: &mut Config, warnings: &mut Vec<String>) {
+    if !is_known_quality(&config.default_quality) {
+        let default = Config::default().default_quality;
+        warnings.push(format!(
+            "unknown default_quality \"{}\", using \"{}\" (use one of {} or a height like \"1440p\")",
+            config.default_quality,
+            default,
+            QUALITY_PRESETS.join(", ")
+        ));
+        config.default_quality = default;
+    }
+
+    for (name, profile) in &mut config.profiles {
+        if let Some(quality) = &profile.quality {
+            if !is_known_quality(quality) {
+                warnings.push(format!(
+                    "unknown quality \"{}\" in profile \"{}\", using default_quality",
+                    quality, name
+                ));
+                profile.quality = None;
+            }
+        }
+    }
+
+    // A rule for a quality no job can have would never match
+    config.output_rules.retain(|rule| {
+        let known = rule.quality.as_deref().is_none_or(is_known_quality);
+        if !known {
+            warnings.push(format!(
+                "unknown quality \"{}\" in output_rules, ignoring the rule",
+                rule.quality.as_deref().unwrap_or_default()
+            ));
+        }
+        known
+    });
+}
+
+/// Fall back to the default output directory if the configured one can't be
+/// created or written to, e.g. on a drive that isn't mounted
+fn validate_output_directory(config: &mut Config, warnings: &mut Vec<String>) {
+    if is_writable(Path::new(&config.output_directory)) {
+        return;
+    }
+
+    let default = Config::default().output_directory;
+    warnings.push(format!(
+        "output_directory \"{}\" is not writable, using \"{}\"",
+        config.output_directory, default
+    ));
+    config.output_directory = default;
+}
+
+/// Replace invalid encoder settings with the defaults
+fn validate_ffmpeg_settings(config: &mut Config, warnings: &mut Vec<String>) {
+    let defaults = Config::default();
+
+    if !X264_PRESETS.contains(&config.ffmpeg_preset.as_str()) {
+        warnings.push(format!(
+            "unknown ffmpeg_preset \"{}\", using \"{}\"",
+            config.ffmpeg_preset, defaults.ffmpeg_preset
+        ));
+        config.ffmpeg_preset = defaults.ffmpeg_preset;
+    }
+
+    if let Some(hw_accel) = &config.hw_accel {
+        if !HW_ACCELS.contains(&hw_accel.as_str()) {
+            warnings.push(format!(
+                "unknown hw_accel \"{}\", encoding in software",
+                hw_accel
+            ));
+            config.hw_accel = None;
+        }
+    }
+
+    if config.ffmpeg_crf > 51 {
+        warnings.push(format!(
+            "ffmpeg_crf {} is outside 0-51, using {}",
+            config.ffmpeg_crf, defaults.ffmpeg_crf
+        ));
+        config.ffmpeg_crf = defaults.ffmpeg_crf;
+    }
+
+    if let Some(bitrate) = &config.target_bitrate {
+        let pattern = Regex::new(r"^\d+(\.\d+)?[KkMm]?$").expect("valid regex");
+        if !pattern.is_match(bitrate) {
+            warnings.push(format!(
+                "invalid target_bitrate \"{}\", encoding with ffmpeg_crf",
+                bitrate
+            ));
+            config.target_bitrate = None;
+        } else if config.ffmpeg_crf != defaults.ffmpeg_crf {
+            // Saved configs always contain ffmpeg_crf, so only a changed one
+            // is a conflict
+            warnings.push(format!(
+                "ffmpeg_crf and target_bitrate can't be combined, ignoring ffmpeg_crf {}",
+                config.ffmpeg_crf
+            ));
+            config.ffmpeg_crf = defaults.ffmpeg_crf;
+        }
+    }
+}
+
+/// Drop a target resolution that isn't "WIDTHxHEIGHT"
+fn validate_target_resolution(config: &mut Config, warnings: &mut Vec<String>) {
+    let Some(resolution) = &config.target_resolution else {
+        return;
+    };
+
+    if parse_resolution(resolution).is_none() {
+        warnings.push(format!(
+            "invalid target_resolution \"{}\", videos won't be scaled",
+            resolution
+        ));
+        config.target_resolution = None;
+    }
+}
+
+/// Drop a frame rate outside 1-240 fps, likely a typo
+fn validate_target_fps(config: &mut Config, warnings: &mut Vec<String>) {
+    if let Some(fps) = config.target_fps {
+        if !(1.0..=240.0).contains(&fps) {
+            warnings.push(format!(
+                "target_fps {} is outside 1-240, keeping the frame rate",
+                fps
+            ));
+            config.target_fps = None;
+        }
+    }
+}
+
+/// Drop rate limits yt-dlp wouldn't understand, it expects a number with
+/// an optional K or M suffix
+fn validate_rate_limit(config: &mut Config, warnings: &mut Vec<String>) {
+    let pattern = Regex::new(r"^\d+(\.\d+)?[KkMm]?$").expect("valid regex");
+    for (name, rate_limit) in [
+        ("rate_limit", &mut config.rate_limit),
+        ("total_rate_limit", &mut config.total_rate_limit),
+    ] {
+        if let Some(limit) = rate_limit {
+            if !pattern.is_match(limit) {
+                warnings.push(format!(
+                    "invalid {} \"{}\", downloads won't be limited by it",
+                    name, limit
+                ));
+                *rate_limit = None;
+            }
+        }
+    }
+}
+
+/// Reset an audio quality yt-dlp would refuse: 0-10, or a bitrate with a K
+/// suffix
+fn validate_audio_quality(config: &mut Config, warnings: &mut Vec<String>) {
+    let quality = config.audio_quality.trim();
+    let valid = match quality.strip_suffix(['K', 'k']) {
+        Some(bitrate) => bitrate.parse::<u32>().is_ok_and(|kbps| kbps > 0),
+        None => quality.parse::<u8>().is_ok_and(|q| q <= 10),
+    };
+    if !valid {
+        let default = Config::default().audio_quality;
+        warnings.push(format!(
+            "invalid audio_quality \"{}\", use 0-10 or a bitrate like \"320K\", using {}",
+            config.audio_quality, default
+        ));
+        config.audio_quality = default;
+    }
+}
+
+/// Reset an output template that would make downloads overwrite each other
+/// or lose their extension
+fn validate_output_template(config: &mut Config, warnings: &mut Vec<String>) {
+    if !config.output_template.contains("%(ext)s") {
+        let default = Config::default().output_template;
+        warnings.push(format!(
+            "output_template \"{}\" must contain %(ext)s, using \"{}\"",
+            config.output_template, default
+        ));
+        config.output_template = default;
+    }
+}
+
+/// Drop format selectors with an empty alternative, which would leave
+/// yt-dlp a malformed -f argument. Without any left, the quality presets
+/// are used
+fn validate_format_fallbacks(config: &mut Config, warnings: &mut Vec<String>) {
+    config.format_fallbacks.retain(|selector| {
+        let valid = selector.split('/').all(|part| !part.trim().is_empty());
+        if !valid {
+            warnings.push(format!(
+                "format_fallbacks entry \"{}\" has an empty selector, ignoring it",
+                selector
+            ));
+        }
+        valid
+    });
+}
+
+/// Ignore a cookies file that doesn't exist, yt-dlp would fail every download
+fn validate_cookies_file(config: &mut Config, warnings: &mut Vec<String>) {
+    if let Some(path) = &config.cookies_file {
+        if !path.is_file() {
+            warnings.push(format!(
+                "cookies_file {} not found, downloading without cookies",
+                path.display()
+            ));
+            config.cookies_file = None;
+        }
+    }
+}
+
+/// Drop SponsorBlock categories yt-dlp doesn't know, it would refuse to run
+fn validate_sponsorblock(config: &mut Config, warnings: &mut Vec<String>) {
+    config.sponsorblock_remove.retain(|category| {
+        let known = SPONSORBLOCK_CATEGORIES.contains(&category.as_str());
+        if !known {
+            warnings.push(format!(
+                "unknown sponsorblock_remove category \"{}\", ignoring it",
+                category
+            ));
+        }
+        known
+    });
+}
+
+/// Ignore file name lengths that leave too little of the title, or that no
+/// filesystem allows
+fn validate_max_filename_length(config: &mut Config, warnings: &mut Vec<String>) {
+    if let Some(length) = config.max_filename_length {
+        if !(MIN_FILENAME_LENGTH..=MAX_FILENAME_LENGTH).contains(&length) {
+            warnings.push(format!(
+                "max_filename_length {} is not between {} and {}, not limiting file names",
+                length, MIN_FILENAME_LENGTH, MAX_FILENAME_LENGTH
+            ));
+            config.max_filename_length = None;
+        }
+    }
+}
+
+/// Fall back to the PATH for tools that aren't where they're configured
+fn validate_tool_paths(config: &mut Config, warnings: &mut Vec<String>) {
+    for (name, path) in [
+        ("ytdlp_path", &mut config.ytdlp_path),
+        ("ffmpeg_path", &mut config.ffmpeg_path),
+    ] {
+        if let Some(tool) = path {
+            if !tool.is_file() {
+                warnings.push(format!(
+                    "{} {} not found, using the PATH",
+                    name,
+                    tool.display()
+                ));
+                *path = None;
+            }
+        }
+    }
+    let ffprobe = config.ffprobe_program();
+    if config.ffmpeg_path.is_some() && !ffprobe.is_file() {
+        warnings.push(format!(
+            "{} not found next to ffmpeg_path, probing videos will fail",
+            ffprobe.display()
+        ));
+    }
+}
+
+/// Fall back to yt-dlp without a command, and to its own downloader for
+/// ones it can't hand downloads to
+fn validate_downloader(config: &mut Config, warnings: &mut Vec<String>) {
+    if config.downloader_command.trim().is_empty() {
+        warnings.push("downloader_command is empty, using yt-dlp".to_string());
+        config.downloader_command = "yt-dlp".to_string();
+    }
+    if let Some(downloader) = &config.external_downloader {
+        if !EXTERNAL_DOWNLOADERS.contains(&downloader.as_str()) {
+            warnings.push(format!(
+                "unknown external_downloader \"{}\", using yt-dlp's own",
+                downloader
+            ));
+            config.external_downloader = None;
+        }
+    }
+}
+
+/// Fall back to JSON for unknown export formats
+fn validate_export_format(config: &mut Config, warnings: &mut Vec<String>) {
+    if !EXPORT_FORMATS.contains(&config.export_format.as_str()) {
+        warnings.push(format!(
+            "unknown export_format \"{}\", using \"json\"",
+            config.export_format
+        ));
+        config.export_format = "json".to_string();
+    }
+}
+
+fn validate_log_level(config: &mut Config, warnings: &mut Vec<String>) {
+    if config.log_level.parse::<LevelFilter>().is_err() {
+        warnings.push(format!(
+            "unknown log_level \"{}\", using \"info\"",
+            config.log_level
+        ));
+        config.log_level = "info".to_string();
+    }
+}
+
+/// Fall back to no profile if the active one isn't defined
+fn validate_active_profile(config: &mut Config, warnings: &mut Vec<String>) {
+    if let Some(active) = &config.active_profile {
+        if !config.profiles.contains_key(active) {
+            warnings.push(format!(
+                "active_profile \"{}\" is not defined in profiles, using none",
+                active
+            ));
+            config.active_profile = None;
+        }
+    }
+}
+
+/// Only http(s) webhooks can be called
+fn validate_webhook_url(config: &mut Config, warnings: &mut Vec<String>) {
+    if let Some(webhook_url) = &config.webhook_url {
+        if !is_http_url(webhook_url) {
+            warnings.push(format!(
+                "webhook_url \"{}\" is not an http(s) URL, not calling it",
+                webhook_url
+            ));
+            config.webhook_url = None;
+        }
+    }
+}
+
+/// A short stall timeout would fail downloads waiting to be retried, and a
+/// socket timeout of 0 makes yt-dlp give up right away
+fn validate_timeouts(config: &mut Config, warnings: &mut Vec<String>) {
+    let stall_timeout = config.stall_timeout_secs;
+    if stall_timeout != 0 && stall_timeout < MIN_STALL_TIMEOUT_SECS {
+        warnings.push(format!(
+            "stall_timeout_secs {} is below {}, using {}",
+            stall_timeout, MIN_STALL_TIMEOUT_SECS, MIN_STALL_TIMEOUT_SECS
+        ));
+        config.stall_timeout_secs = MIN_STALL_TIMEOUT_SECS;
+    }
+    if config.socket_timeout_secs == Some(0) {
+        warnings.push("socket_timeout_secs is 0, using yt-dlp's default".to_string());
+        config.socket_timeout_secs = None;
+    }
+}
+
+/// Connect as usual rather than with an IP version or address yt-dlp rejects
+fn validate_network(config: &mut Config, warnings: &mut Vec<String>) {
+    if let Some(version) = config.force_ip_version {
+        if version != 4 && version != 6 {
+            warnings.push(format!(
+                "force_ip_version {} is neither 4 nor 6, using either",
+                version
+            ));
+            config.force_ip_version = None;
+        }
+    }
+    if let Some(address) = &config.source_address {
+        if address.parse::<IpAddr>().is_err() {
+            warnings.push(format!(
+                "source_address \"{}\" is not an IP address, not binding to it",
+                address
+            ));
+            config.source_address = None;
+        }
+    }
+}
+
+/// Drop theme colors that aren't hex colors, they keep the default
+fn validate_theme(config: &mut Config, warnings: &mut Vec<String>) {
+    for (role, color) in config.theme.colors_mut() {
+        if let Some(hex) = color {
+            if parse_hex(hex).is_none() {
+                warnings.push(format!(
+                    "theme color {} \"{}\" is not a hex color like \"#64a0c8\", using the default",
+                    role, hex
+                ));
+                *color = None;
+            }
+        }
+    }
+}
+
+/// Save a change made in carbon to the config file. `change` is applied to
+/// the file as written, so environment overrides and the defaults replacing
+/// invalid settings stay out of it
+pub fn update_config(change: impl FnOnce(&mut Config)) -> Result<()> {
+    let contents = fs::read_to_string(config_path()?)?;
+    let mut config: Config = toml::from_str(&contents)?;
+    change(&mut config);
+    save_config(&config)
+}
+
 pub fn save_config(config: &Config) -> Result<()> {
     let path = config_path()?;
     let contents = toml::to_string_pretty(config)?;
     fs::write(&path, contents)?;
     Ok(())
 }
+
+/// Load the jobs saved by the previous session. Unfinished jobs are queued
+/// again and completed jobs whose output is gone are dropped.
+pub fn load_jobs() -> Result<Vec<Job>> {
+    let path = jobs_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&path)?;
+    let saved: Vec<Job> = serde_json::from_str(&contents)?;
+
+    let jobs = saved
+        .into_iter()
+        .filter_map(|mut job| match job.status() {
+            JobStatus::Complete => job
+                .output_path
+                .as_ref()
+                .is_some_and(|path| path.exists())
+                .then_some(job),
+            JobStatus::Cancelled => Some(job),
+            _ => {
+                job.reset_for_retry();
+                Some(job)
+            }
+        })
+        .collect();
+
+    Ok(jobs)
+}
+
+/// Totals of everything downloaded with carbon, zero before the first
+/// download
+pub fn load_stats() -> Result<DownloadStats> {
+    let path = stats_path()?;
+    if !path.exists() {
+        return Ok(DownloadStats::default());
+    }
+    Ok(toml::from_str(&fs::read_to_string(path)?)?)
+}
+
+pub fn save_stats(stats: &DownloadStats) -> Result<()> {
+    fs::write(stats_path()?, toml::to_string_pretty(stats)?)?;
+    Ok(())
+}
+
+pub fn save_jobs(jobs: &[Job]) -> Result<()> {
+    let path = jobs_path()?;
+    let contents = serde_json::to_string_pretty(jobs)?;
+    fs::write(&path, contents)?;
+    Ok(())
+}
@@ -1,162 +1,530 @@
-use crate::models::JobUpdate;
+use crate::models::{Config, ConversionProfile, JobUpdate};
 use color_eyre::Result;
 use regex::Regex;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
+/// Integrated loudness, true peak and loudness range to normalize audio to
+const LOUDNORM_FILTER: &str = "loudnorm=I=-16:TP=-1.5:LRA=11";
+
+/// Longest file name most filesystems allow, in bytes
+const MAX_FILE_NAME_BYTES: usize = 255;
+
+/// Settings for a single conversion, resolved from the config
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    pub ffmpeg: PathBuf,
+    pub ffprobe: PathBuf,
+    pub output_dir: PathBuf,
+    pub profile: ConversionProfile,
+    /// Appended to the file name of converted files
+    pub suffix: String,
+    pub preset: String,
+    pub crf: u8,
+    /// Video bitrate to encode at instead of crf, e.g. "8M"
+    pub target_bitrate: Option<String>,
+    /// Hardware encoder family for H.264: "nvenc", "videotoolbox" or "qsv"
+    pub hw_accel: Option<String>,
+    /// ffmpeg scale filter size, e.g. "1920:-2"
+    pub scale: Option<String>,
+    /// Frame rate to convert videos to
+    pub fps: Option<f64>,
+    /// Normalize the audio loudness
+    pub normalize_audio: bool,
+    /// Extra output arguments, added after carbon's own
+    pub extra_args: Vec<String>,
+    /// Remove the input once converted, true for downloads
+    pub delete_input: bool,
+    /// Move an input that would be removed into the output directory instead
+    pub keep_original: bool,
+}
+
+/// What a conversion needs to know about its input, probed once per file
+#[derive(Debug, Clone, Default)]
+pub struct MediaInfo {
+    /// Seconds, 0 if unknown
+    pub duration: u64,
+    /// Frames of the main video stream according to the container, 0 if
+    /// unknown
+    nb_frames: u64,
+    /// Average frame rate of the main video stream, 0 if unknown
+    frame_rate: f64,
+    /// Seconds of the main video stream, if it differs from the container's
+    stream_duration: Option<f64>,
+    /// Index of the embedded cover art stream, if the file has one
+    cover_stream: Option<usize>,
+}
+
+impl MediaInfo {
+    /// Number of frames the conversion will output, 0 if unknown. Uses the
+    /// frame count of the container if it has one, otherwise the duration
+    /// times the frame rate, which is also used when converting to `fps`
+    fn frame_count(&self, fps: Option<f64>) -> u64 {
+        let duration = self.stream_duration.unwrap_or(self.duration as f64);
+        match fps {
+            Some(fps) => (duration * fps) as u64,
+            None if self.nb_frames > 0 => self.nb_frames,
+            None => (duration * self.frame_rate) as u64,
+        }
+    }
+}
+
+impl ConvertOptions {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            ffmpeg: config.ffmpeg_program(),
+            ffprobe: config.ffprobe_program(),
+            output_dir: PathBuf::from(&config.output_directory),
+            profile: config.conversion_profile,
+            suffix: config
+                .converted_suffix
+                .clone()
+                .unwrap_or_else(|| config.conversion_profile.suffix().to_string()),
+            preset: config.ffmpeg_preset.clone(),
+            crf: config.ffmpeg_crf,
+            target_bitrate: config.target_bitrate.clone(),
+            hw_accel: config.hw_accel.clone(),
+            scale: config
+                .target_resolution
+                .as_deref()
+                .and_then(parse_resolution)
+                .map(|(width, height)| format!("{}:{}", scale_side(width), scale_side(height))),
+            fps: config.target_fps,
+            normalize_audio: config.normalize_audio,
+            extra_args: config.ffmpeg_extra_args.clone(),
+            delete_input: true,
+            keep_original: config.keep_original,
+        }
+    }
+}
+
 pub async fn convert_for_davinci(
     job_id: Uuid,
     input_path: PathBuf,
-    output_dir: PathBuf,
+    options: ConvertOptions,
+    media: &MediaInfo,
     update_tx: mpsc::UnboundedSender<(Uuid, JobUpdate)>,
 ) -> Result<PathBuf> {
-    // Ensure output directory exists
-    tokio::fs::create_dir_all(&output_dir).await?;
-
-    // Generate output filename
-    let file_stem = input_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .ok_or_else(|| color_eyre::eyre::eyre!("Invalid input filename"))?;
+    // Re-encode video to H.264 with PCM audio to ensure compatibility
+    let crf = options.crf.to_string();
+    let bitrate = options.target_bitrate.as_deref();
+    let encoder = hw_encoder(options.hw_accel.as_deref());
+    let video_args = match (options.hw_accel.as_deref(), bitrate) {
+        (Some("nvenc"), Some(bitrate)) => vec![
+            "-c:v", encoder, "-preset", "p5", "-rc", "vbr", "-b:v", bitrate,
+        ],
+        (Some("nvenc"), None) => vec![
+            "-c:v", encoder, "-preset", "p5", // Balanced speed and quality
+            "-rc", "vbr", "-cq", &crf, // Constant quality, on the same scale as crf
+        ],
+        (Some("qsv"), Some(bitrate)) => vec!["-c:v", encoder, "-b:v", bitrate],
+        (Some("qsv"), None) => vec!["-c:v", encoder, "-global_quality", &crf],
+        // VideoToolbox has no constant quality mode, so use a high bitrate
+        (Some("videotoolbox"), bitrate) => {
+            vec!["-c:v", encoder, "-b:v", bitrate.unwrap_or("40M")]
+        }
+        (_, Some(bitrate)) => vec![
+            "-c:v",
+            encoder,
+            "-preset",
+            &options.preset,
+            "-b:v",
+            bitrate, // Average bitrate, hit closely thanks to the first pass
+        ],
+        _ => vec![
+            "-c:v",
+            encoder, // Re-encode to H.264 for better compatibility
+            "-preset",
+            &options.preset, // Encoding speed, slower presets compress better
+            "-crf",
+            &crf, // Quality (lower = better quality, 18 is visually lossless)
+        ],
+    };
 
-    let output_path = output_dir.join(format!("{}_davinci.mp4", file_stem));
+    let output_path = converted_output_path(&input_path, &options)?;
+    let result = if bitrate.is_some() && options.hw_accel.is_none() {
+        run_two_pass(
+            job_id,
+            &input_path,
+            &output_path,
+            &video_args,
+            &options,
+            media,
+            update_tx,
+        )
+        .await
+    } else {
+        run_ffmpeg(
+            job_id,
+            &input_path,
+            &output_path,
+            &video_args,
+            &options,
+            media,
+            update_tx,
+        )
+        .await
+    };
+    result.map_err(|e| {
+        // Point out the config change that gets conversions working again
+        if options.hw_accel.is_some() && is_encoder_error(&e.to_string()) {
+            color_eyre::eyre::eyre!(
+                "{}\nhint: the {} encoder isn't available, remove hw_accel from the config to encode in software",
+                e,
+                encoder
+            )
+        } else {
+            e
+        }
+    })?;
 
-    // FFmpeg command to convert for DaVinci Resolve compatibility
-    // Re-encode video to H.264 with PCM audio to ensure compatibility
-    let mut child = Command::new("ffmpeg")
-        .arg("-i")
-        .arg(&input_path)
-        .arg("-c:v")
-        .arg("libx264") // Re-encode to H.264 for better compatibility
-        .arg("-preset")
-        .arg("fast") // Faster encoding while maintaining quality
-        .arg("-crf")
-        .arg("18") // High quality (lower = better quality, 18 is visually lossless)
-        .arg("-c:a")
-        .arg("pcm_s16le") // Convert audio to PCM 16-bit little-endian
-        .arg("-ar")
-        .arg("48000") // Sample rate 48kHz (standard for video)
-        .arg("-progress")
-        .arg("pipe:1") // Output progress to stdout
-        .arg("-y") // Overwrite output file if exists
-        .arg(&output_path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?;
+    Ok(output_path)
+}
 
-    let stdout = child.stdout.take().expect("Failed to capture stdout");
-    let stderr = child.stderr.take().expect("Failed to capture stderr");
+/// Encode in two passes, the first only analyses the video so the second can
+/// spread the bitrate well. Each pass is half of the progress.
+async fn run_two_pass(
+    job_id: Uuid,
+    input_path: &Path,
+    output_path: &Path,
+    video_args: &[&str],
+    options: &ConvertOptions,
+    media: &MediaInfo,
+    update_tx: mpsc::UnboundedSender<(Uuid, JobUpdate)>,
+) -> Result<()> {
+    // ffmpeg appends "-0.log" (and ".mbtree" for x264) to the prefix
+    let log_prefix = std::env::temp_dir().join(format!("carbon-{}", job_id));
+    let log_prefix_arg = log_prefix.to_string_lossy().to_string();
+    let null_output = Path::new(if cfg!(windows) { "NUL" } else { "/dev/null" });
 
-    let mut stdout_reader = BufReader::new(stdout).lines();
-    let stderr_reader = BufReader::new(stderr).lines();
+    let mut first_args = video_args.to_vec();
+    first_args.extend(["-pass", "1", "-passlogfile", &log_prefix_arg, "-f", "null"]);
+    let mut second_args = video_args.to_vec();
+    second_args.extend(["-pass", "2", "-passlogfile", &log_prefix_arg]);
 
-    // Get video duration first for progress calculation
-    let duration = get_video_duration(&input_path).await?;
+    let mut result = run_ffmpeg(
+        job_id,
+        input_path,
+        null_output,
+        &first_args,
+        options,
+        media,
+        pass_progress(&update_tx, 0),
+    )
+    .await;
+    if result.is_ok() {
+        result = run_ffmpeg(
+            job_id,
+            input_path,
+            output_path,
+            &second_args,
+            options,
+            media,
+            pass_progress(&update_tx, 1),
+        )
+        .await;
+    }
 
-    // Regex to parse progress output
-    let time_regex = Regex::new(r"out_time_ms=(\d+)")?;
+    for extension in ["-0.log", "-0.log.mbtree"] {
+        let mut path = log_prefix.clone().into_os_string();
+        path.push(extension);
+        let _ = tokio::fs::remove_file(path).await;
+    }
+    result
+}
 
-    // Read progress output
-    let update_tx_clone = update_tx.clone();
-    let job_id_clone = job_id;
+/// A sender that scales the progress of one of two passes to its half of
+/// the whole conversion
+fn pass_progress(
+    update_tx: &mpsc::UnboundedSender<(Uuid, JobUpdate)>,
+    pass: u8,
+) -> mpsc::UnboundedSender<(Uuid, JobUpdate)> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let update_tx = update_tx.clone();
     tokio::spawn(async move {
-        while let Ok(Some(line)) = stdout_reader.next_line().await {
-            if let Some(caps) = time_regex.captures(&line) {
-                if let Ok(time_ms) = caps[1].parse::<u64>() {
-                    let time_sec = time_ms / 1_000_000;
-                    if duration > 0 {
-                        let percent = (time_sec as f64 / duration as f64 * 100.0).min(100.0);
-                        let _ = update_tx_clone.send((job_id_clone, JobUpdate::Progress(percent)));
-                    }
+        while let Some((job_id, update)) = rx.recv().await {
+            let update = match update {
+                JobUpdate::Progress(percent) => {
+                    JobUpdate::Progress(f64::from(pass) * 50.0 + percent / 2.0)
                 }
+                update => update,
+            };
+            if update_tx.send((job_id, update)).is_err() {
+                break;
             }
         }
     });
+    tx
+}
 
-    // Capture stderr for errors
-    let mut stderr_output = Vec::new();
-    let mut stderr_lines = stderr_reader;
-    while let Ok(Some(line)) = stderr_lines.next_line().await {
-        stderr_output.push(line);
+/// Parse a "WIDTHxHEIGHT" resolution, either side may be -1 to keep the
+/// aspect ratio
+pub fn parse_resolution(resolution: &str) -> Option<(i32, i32)> {
+    let (width, height) = resolution.trim().split_once('x')?;
+    let side = |s: &str| match s.parse::<i32>() {
+        Ok(n) if n > 0 || n == -1 => Some(n),
+        _ => None,
+    };
+    match (side(width)?, side(height)?) {
+        (-1, -1) => None,
+        size => Some(size),
     }
+}
 
-    // Wait for process to complete
-    let status = child.wait().await?;
-
-    if !status.success() {
-        let error_msg = stderr_output.join("\n");
-        return Err(color_eyre::eyre::eyre!(
-            "FFmpeg conversion failed: {}",
-            error_msg
-        ));
+/// Side of the scale filter, -2 keeps the aspect ratio like -1 but rounds to
+/// an even size as the encoders require
+fn scale_side(side: i32) -> i32 {
+    if side < 0 {
+        -2
+    } else {
+        side
     }
-
-    // Delete the original temp file
-    let _ = tokio::fs::remove_file(&input_path).await;
-
-    Ok(output_path)
 }
 
-async fn get_video_duration(path: &PathBuf) -> Result<u64> {
-    let output = Command::new("ffprobe")
-        .arg("-v")
-        .arg("error")
-        .arg("-show_entries")
-        .arg("format=duration")
-        .arg("-of")
-        .arg("default=noprint_wrappers=1:nokey=1")
-        .arg(path)
-        .output()
-        .await?;
-
-    if output.status.success() {
-        let duration_str = String::from_utf8_lossy(&output.stdout);
-        let duration: f64 = duration_str.trim().parse().unwrap_or(0.0);
-        Ok(duration as u64)
-    } else {
-        Ok(0)
+/// H.264 encoder for a hardware acceleration setting, software if none
+fn hw_encoder(hw_accel: Option<&str>) -> &'static str {
+    match hw_accel {
+        Some("nvenc") => "h264_nvenc",
+        Some("videotoolbox") => "h264_videotoolbox",
+        Some("qsv") => "h264_qsv",
+        _ => "libx264",
     }
 }
 
+/// Whether ffmpeg failed because the encoder couldn't be used, e.g. missing
+/// from the build or without a supported device
+fn is_encoder_error(stderr: &str) -> bool {
+    [
+        "Unknown encoder",
+        "Error while opening encoder",
+        "Cannot load",
+        "No capable devices found",
+        "No NVENC capable devices found",
+        "Device creation failed",
+        "Error initializing output stream",
+    ]
+    .iter()
+    .any(|pattern| stderr.contains(pattern))
+}
+
 // Alternative: Convert to DNxHD for even better DaVinci Resolve compatibility
-#[allow(dead_code)]
 pub async fn convert_to_dnxhd(
     job_id: Uuid,
     input_path: PathBuf,
-    output_dir: PathBuf,
+    options: ConvertOptions,
+    media: &MediaInfo,
+    update_tx: mpsc::UnboundedSender<(Uuid, JobUpdate)>,
+) -> Result<PathBuf> {
+    let video_args = [
+        "-c:v",
+        "dnxhd", // DNxHD codec
+        "-profile:v",
+        "dnxhr_hq", // High quality profile
+    ];
+
+    let output_path = converted_output_path(&input_path, &options)?;
+    run_ffmpeg(
+        job_id,
+        &input_path,
+        &output_path,
+        &video_args,
+        &options,
+        media,
+        update_tx,
+    )
+    .await?;
+
+    Ok(output_path)
+}
+
+// Intermediate codec preferred by Final Cut Pro and Premiere workflows
+pub async fn convert_to_prores(
+    job_id: Uuid,
+    input_path: PathBuf,
+    options: ConvertOptions,
+    media: &MediaInfo,
     update_tx: mpsc::UnboundedSender<(Uuid, JobUpdate)>,
 ) -> Result<PathBuf> {
-    tokio::fs::create_dir_all(&output_dir).await?;
+    let video_args = [
+        "-c:v",
+        "prores_ks", // ProRes codec
+        "-profile:v",
+        "3", // ProRes 422 HQ
+    ];
 
+    let output_path = converted_output_path(&input_path, &options)?;
+    run_ffmpeg(
+        job_id,
+        &input_path,
+        &output_path,
+        &video_args,
+        &options,
+        media,
+        update_tx,
+    )
+    .await?;
+
+    Ok(output_path)
+}
+
+/// Path of the file converted from `input_path` with the given options
+pub fn converted_output_path(input_path: &Path, options: &ConvertOptions) -> Result<PathBuf> {
     let file_stem = input_path
         .file_stem()
         .and_then(|s| s.to_str())
         .ok_or_else(|| color_eyre::eyre::eyre!("Invalid input filename"))?;
 
-    let output_path = output_dir.join(format!("{}_dnxhd.mov", file_stem));
+    let extension = format!("{}.{}", options.suffix, options.profile.extension());
+    let stem = sanitize_file_stem(
+        file_stem,
+        MAX_FILE_NAME_BYTES.saturating_sub(extension.len()),
+    );
+    Ok(options.output_dir.join(format!("{}{}", stem, extension)))
+}
+
+/// A file stem that's valid on every common filesystem, Windows included:
+/// reserved characters replaced with "_", no trailing dots or spaces, no
+/// device names like "CON", and at most `max_bytes` long
+fn sanitize_file_stem(stem: &str, max_bytes: usize) -> String {
+    let mut sanitized: String = stem
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    // Cut at a character boundary
+    if sanitized.len() > max_bytes {
+        let mut end = max_bytes;
+        while !sanitized.is_char_boundary(end) {
+            end -= 1;
+        }
+        sanitized.truncate(end);
+    }
+
+    let trimmed_len = sanitized.trim_end_matches(['.', ' ']).len();
+    sanitized.truncate(trimmed_len);
+    if sanitized.is_empty() {
+        return "video".to_string();
+    }
+
+    // Windows reserves these names whatever the extension
+    let device = sanitized
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_uppercase();
+    let reserved = matches!(device.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || ((device.starts_with("COM") || device.starts_with("LPT"))
+            && device.len() == 4
+            && device.ends_with(|c: char| c.is_ascii_digit() && c != '0'));
+    if reserved {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
 
-    let mut child = Command::new("ffmpeg")
-        .arg("-i")
-        .arg(&input_path)
-        .arg("-c:v")
-        .arg("dnxhd") // DNxHD codec
-        .arg("-profile:v")
-        .arg("dnxhr_hq") // High quality profile
+/// Where to keep the original of a conversion: the output directory under
+/// its own name, or with "_original" appended when that's the converted
+/// file or already taken
+pub fn kept_original_path(input_path: &Path, output_path: &Path, output_dir: &Path) -> PathBuf {
+    let stem = input_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = input_path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    // With room for "_original" and a counter
+    let max_bytes = MAX_FILE_NAME_BYTES.saturating_sub(extension.len() + "_original99".len());
+    let stem = sanitize_file_stem(&stem, max_bytes);
+    let mut candidate = output_dir.join(format!("{}{}", stem, extension));
+    let mut counter = 1;
+    while candidate == output_path || candidate.exists() {
+        let suffix = if counter == 1 {
+            "_original".to_string()
+        } else {
+            format!("_original{}", counter)
+        };
+        candidate = output_dir.join(format!("{}{}{}", stem, suffix, extension));
+        counter += 1;
+    }
+    candidate
+}
+
+/// Run ffmpeg with the given video codec arguments and the scaling and frame
+/// rate of the options, reporting progress.
+/// Audio is always converted to PCM, which DaVinci Resolve on Linux needs.
+async fn run_ffmpeg(
+    job_id: Uuid,
+    input_path: &Path,
+    output_path: &Path,
+    video_args: &[&str],
+    options: &ConvertOptions,
+    media: &MediaInfo,
+    update_tx: mpsc::UnboundedSender<(Uuid, JobUpdate)>,
+) -> Result<()> {
+    // Ensure output directory exists
+    if let Some(output_dir) = output_path.parent() {
+        tokio::fs::create_dir_all(output_dir).await?;
+    }
+
+    let mut command = Command::new(&options.ffmpeg);
+    command.arg("-i").arg(input_path).args(video_args);
+    // Only the main video, cover art is copied and can't be filtered
+    if let Some(scale) = &options.scale {
+        command.arg("-filter:v:0").arg(format!("scale={}", scale));
+    }
+    if let Some(fps) = options.fps {
+        command.arg("-r:v:0").arg(fps.to_string());
+    }
+
+    // ffmpeg drops cover art by default, and would re-encode it as video
+    if let Some(cover) = media.cover_stream {
+        command
+            .arg("-map")
+            .arg("0:V:0")
+            .arg("-map")
+            .arg("0:a?")
+            .arg("-map")
+            .arg(format!("0:{}", cover))
+            .arg("-c:v:1")
+            .arg("copy")
+            .arg("-disposition:v:1")
+            .arg("attached_pic");
+    }
+
+    // Single pass loudnorm, to the EBU R128 style target common for online
+    // video. It resamples to 192kHz, -ar below brings it back to 48kHz
+    if options.normalize_audio {
+        command.arg("-af").arg(LOUDNORM_FILTER);
+    }
+
+    command
         .arg("-c:a")
-        .arg("pcm_s16le")
+        .arg("pcm_s16le") // Convert audio to PCM 16-bit little-endian
         .arg("-ar")
-        .arg("48000")
+        .arg("48000") // Sample rate 48kHz (standard for video)
+        .arg("-map_metadata")
+        .arg("0") // Keep title, artist etc. of the input
+        .args(&options.extra_args) // Later options override earlier ones
         .arg("-progress")
-        .arg("pipe:1")
-        .arg("-y")
-        .arg(&output_path)
+        .arg("pipe:1") // Output progress to stdout
+        .arg("-y") // Overwrite output file if exists
+        .arg(output_path)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .spawn()?;
+        .kill_on_drop(true);
+    tracing::debug!(job = %job_id, command = ?command.as_std(), "running ffmpeg");
+    let mut child = command.spawn()?;
 
     let stdout = child.stdout.take().expect("Failed to capture stdout");
     let stderr = child.stderr.take().expect("Failed to capture stderr");
@@ -164,31 +532,53 @@ pub async fn convert_to_dnxhd(
     let mut stdout_reader = BufReader::new(stdout).lines();
     let stderr_reader = BufReader::new(stderr).lines();
 
-    let duration = get_video_duration(&input_path).await?;
+    // Counting frames is more accurate than the timestamp, which runs ahead
+    // or behind with some codecs. Streams where neither is known keep
+    // progress at 0, shown as indeterminate
+    let total_frames = media.frame_count(options.fps);
+    let duration = media.duration;
+    let frame_regex = Regex::new(r"^frame=(\d+)")?;
     let time_regex = Regex::new(r"out_time_ms=(\d+)")?;
 
+    // Read progress output
     let update_tx_clone = update_tx.clone();
     let job_id_clone = job_id;
     tokio::spawn(async move {
+        let mut last_percent = 0.0;
         while let Ok(Some(line)) = stdout_reader.next_line().await {
-            if let Some(caps) = time_regex.captures(&line) {
-                if let Ok(time_ms) = caps[1].parse::<u64>() {
-                    let time_sec = time_ms / 1_000_000;
-                    if duration > 0 {
-                        let percent = (time_sec as f64 / duration as f64 * 100.0).min(100.0);
-                        let _ = update_tx_clone.send((job_id_clone, JobUpdate::Progress(percent)));
-                    }
+            let percent = if total_frames > 0 {
+                frame_regex
+                    .captures(&line)
+                    .and_then(|caps| caps[1].parse::<u64>().ok())
+                    .map(|frame| frame as f64 / total_frames as f64 * 100.0)
+            } else if duration > 0 {
+                time_regex
+                    .captures(&line)
+                    .and_then(|caps| caps[1].parse::<u64>().ok())
+                    .map(|time_us| time_us as f64 / 1_000_000.0 / duration as f64 * 100.0)
+            } else {
+                None
+            };
+
+            // Never move backwards or past the end
+            if let Some(percent) = percent {
+                let percent = percent.clamp(last_percent, 100.0);
+                if percent > last_percent {
+                    last_percent = percent;
+                    let _ = update_tx_clone.send((job_id_clone, JobUpdate::Progress(percent)));
                 }
             }
         }
     });
 
+    // Capture stderr for errors
     let mut stderr_output = Vec::new();
     let mut stderr_lines = stderr_reader;
     while let Ok(Some(line)) = stderr_lines.next_line().await {
         stderr_output.push(line);
     }
 
+    // Wait for process to complete
     let status = child.wait().await?;
 
     if !status.success() {
@@ -199,7 +589,64 @@ pub async fn convert_to_dnxhd(
         ));
     }
 
-    let _ = tokio::fs::remove_file(&input_path).await;
+    Ok(())
+}
 
-    Ok(output_path)
+/// Probe the duration, main video stream and cover art of a file in one
+/// ffprobe run. Whatever can't be determined is left unknown
+pub async fn probe_media(ffprobe: &Path, path: &Path) -> MediaInfo {
+    let mut info = MediaInfo::default();
+    let output = match Command::new(ffprobe)
+        .arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg("format=duration:stream=index,codec_type,nb_frames,avg_frame_rate,duration:stream_disposition=attached_pic")
+        .arg("-of")
+        .arg("json")
+        .arg(path)
+        .output()
+        .await
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return info,
+    };
+    let Ok(probe) = serde_json::from_slice::<serde_json::Value>(&output.stdout) else {
+        return info;
+    };
+
+    // Numbers are reported as strings, unknown ones are left out
+    let number = |value: &serde_json::Value, key: &str| -> Option<f64> {
+        value.get(key)?.as_str()?.parse().ok()
+    };
+    info.duration = number(&probe["format"], "duration").unwrap_or(0.0) as u64;
+
+    let streams = probe["streams"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let (covers, videos): (Vec<_>, Vec<_>) = streams
+        .iter()
+        .filter(|stream| stream["codec_type"] == "video")
+        .partition(|stream| stream["disposition"]["attached_pic"] == 1);
+    info.cover_stream = covers
+        .first()
+        .and_then(|stream| stream["index"].as_u64())
+        .map(|index| index as usize);
+
+    // Only the first video stream is converted
+    if let Some(video) = videos.first() {
+        info.nb_frames = number(video, "nb_frames").unwrap_or(0.0) as u64;
+        info.stream_duration = number(video, "duration");
+        // A fraction like "30000/1001"
+        if let Some((num, den)) = video["avg_frame_rate"]
+            .as_str()
+            .and_then(|rate| rate.split_once('/'))
+        {
+            let (num, den) = (num.parse().unwrap_or(0.0), den.parse().unwrap_or(0.0));
+            if den > 0.0 {
+                info.frame_rate = num / den;
+            }
+        }
+    }
+    info
 }
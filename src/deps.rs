@@ -0,0 +1,125 @@
+use crate::models::Config;
+use regex::Regex;
+use std::path::Path;
+use tokio::process::Command;
+
+/// Versions of the external tools carbon relies on, `None` when a tool
+/// could not be run
+#[derive(Debug, Clone, Default)]
+pub struct Dependencies {
+    pub ytdlp: Option<String>,
+    pub ffmpeg: Option<String>,
+    /// Latest yt-dlp release, once the update check has found it
+    pub ytdlp_latest: Option<String>,
+}
+
+impl Dependencies {
+    /// Names of the tools that are missing
+    pub fn missing(&self) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if self.ytdlp.is_none() {
+            missing.push("yt-dlp");
+        }
+        if self.ffmpeg.is_none() {
+            missing.push("ffmpeg");
+        }
+        missing
+    }
+
+    /// The latest yt-dlp release if it's newer than the installed one
+    pub fn ytdlp_update(&self) -> Option<&str> {
+        let installed = self.ytdlp.as_deref()?;
+        let latest = self.ytdlp_latest.as_deref()?;
+        // Versions are dates like "2024.08.06", nightlies append a build
+        // number, so they compare as strings
+        (latest > installed).then_some(latest)
+    }
+}
+
+/// Check that yt-dlp and ffmpeg can be run, and which versions are installed
+pub async fn check_dependencies(config: &Config) -> Dependencies {
+    let (ytdlp_program, ffmpeg_program) = (config.ytdlp_program(), config.ffmpeg_program());
+    let (ytdlp, ffmpeg) = tokio::join!(
+        tool_version(&ytdlp_program, "--version"),
+        tool_version(&ffmpeg_program, "-version"),
+    );
+
+    Dependencies {
+        // yt-dlp prints just the version, e.g. "2024.08.06"
+        ytdlp,
+        // ffmpeg starts with "ffmpeg version 6.1.1 Copyright ..."
+        ffmpeg: ffmpeg.map(|line| {
+            line.split_whitespace()
+                .nth(2)
+                .map(str::to_string)
+                .unwrap_or(line)
+        }),
+        ytdlp_latest: None,
+    }
+}
+
+/// Ask GitHub for the latest yt-dlp release. Uses curl, and gives up quietly
+/// when it's missing or offline.
+pub async fn latest_ytdlp_version() -> Option<String> {
+    let output = Command::new("curl")
+        .arg("--silent")
+        .arg("--fail")
+        .arg("--location")
+        .arg("--max-time")
+        .arg("10")
+        .arg("https://api.github.com/repos/yt-dlp/yt-dlp/releases/latest")
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let tag_regex = Regex::new(r#""tag_name"\s*:\s*"([^"]+)""#).ok()?;
+    let body = String::from_utf8_lossy(&output.stdout);
+    tag_regex.captures(&body).map(|caps| caps[1].to_string())
+}
+
+/// First line of a tool's version output
+async fn tool_version(program: &Path, arg: &str) -> Option<String> {
+    let output = Command::new(program).arg(arg).output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(stdout.lines().next().unwrap_or_default().trim().to_string())
+}
+
+/// Browsers yt-dlp can read cookies from with --cookies-from-browser
+pub const COOKIE_BROWSERS: [&str; 8] = [
+    "brave", "chrome", "chromium", "edge", "firefox", "opera", "safari", "vivaldi",
+];
+
+/// Browsers of `COOKIE_BROWSERS` whose profile directory exists, looked up
+/// where they keep it on Linux, macOS and Windows
+pub fn installed_browsers() -> Vec<&'static str> {
+    let roots: Vec<_> = [dirs::config_dir(), dirs::data_local_dir(), dirs::home_dir()]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    COOKIE_BROWSERS
+        .into_iter()
+        .filter(|browser| {
+            let profiles: &[&str] = match *browser {
+                "brave" => &["BraveSoftware/Brave-Browser"],
+                "chrome" => &["google-chrome", "Google/Chrome"],
+                "chromium" => &["chromium", "Chromium"],
+                "edge" => &["microsoft-edge", "Microsoft Edge", "Microsoft/Edge"],
+                "firefox" => &[".mozilla/firefox", "Firefox", "Mozilla/Firefox"],
+                "opera" => &["opera", "com.operasoftware.Opera", "Opera Software"],
+                "safari" => &["Library/Containers/com.apple.Safari"],
+                _ => &["vivaldi", "Vivaldi"],
+            };
+            roots
+                .iter()
+                .any(|root| profiles.iter().any(|profile| root.join(profile).is_dir()))
+        })
+        .collect()
+}
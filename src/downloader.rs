@@ -1,57 +1,226 @@
-use crate::models::JobUpdate;
+use crate::models::{parse_size, ClipRange, Config, FormatEntry, JobUpdate, PlaylistEntry};
 use color_eyre::Result;
 use regex::Regex;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::time::Instant;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
-pub struct DownloadProgress {
-    pub percent: f64,
-    pub speed: Option<String>,
-    pub eta: Option<String>,
+/// Settings for a single download, resolved from the config
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Program to run, yt-dlp unless configured otherwise
+    pub command: PathBuf,
+    /// Program yt-dlp downloads with, e.g. "aria2c"
+    pub external_downloader: Option<String>,
+    pub quality: String,
+    /// Exact yt-dlp format code, replaces the quality
+    pub format: Option<String>,
+    /// Format selectors replacing the ones of the quality presets
+    pub format_fallbacks: Vec<String>,
+    pub output_dir: PathBuf,
+    /// yt-dlp output template, relative to the temp directory
+    pub output_template: String,
+    pub write_thumbnail: bool,
+    pub embed_thumbnail: bool,
+    pub embed_metadata: bool,
+    pub user_agent: Option<String>,
+    pub referer: Option<String>,
+    pub rate_limit: Option<String>,
+    pub cookies_file: Option<PathBuf>,
+    pub cookies_from_browser: Option<String>,
+    pub force_ip_version: Option<u8>,
+    pub source_address: Option<String>,
+    pub socket_timeout: Option<u64>,
+    pub sponsorblock_remove: Vec<String>,
+    pub extra_args: Vec<String>,
+    pub audio_only: bool,
+    pub audio_format: String,
+    pub audio_quality: String,
+    /// Only download this part of the video
+    pub clip_range: Option<ClipRange>,
+    /// Longest file name yt-dlp may write, without the extension
+    pub max_filename_length: Option<usize>,
+    /// Continue from the partial file of an interrupted download
+    pub resume: bool,
+    /// Also save each chapter as its own file
+    pub split_chapters: bool,
+}
+
+impl DownloadOptions {
+    pub fn new(config: &Config, url: &str, quality: String, audio_only: bool) -> Self {
+        // Site specific headers take precedence over the global ones
+        let site = url_host(url).and_then(|host| {
+            config
+                .site_headers
+                .iter()
+                .find(|(site, _)| host_matches(host, site))
+                .map(|(_, headers)| headers)
+        });
+
+        Self {
+            // A downloader command replaces yt-dlp, wherever it's installed
+            command: if config.downloader_command == "yt-dlp" {
+                config.ytdlp_program()
+            } else {
+                PathBuf::from(&config.downloader_command)
+            },
+            external_downloader: config.external_downloader.clone(),
+            quality,
+            format: None,
+            format_fallbacks: config.format_fallbacks.clone(),
+            output_dir: PathBuf::from(&config.output_directory),
+            output_template: config.output_template.clone(),
+            write_thumbnail: config.thumbnail_preview,
+            embed_thumbnail: config.embed_thumbnail,
+            embed_metadata: config.embed_metadata,
+            user_agent: site
+                .and_then(|s| s.user_agent.clone())
+                .or_else(|| config.user_agent.clone()),
+            referer: site
+                .and_then(|s| s.referer.clone())
+                .or_else(|| config.referer.clone()),
+            rate_limit: config.rate_limit.clone(),
+            cookies_file: config.cookies_file.clone(),
+            cookies_from_browser: config.cookies_from_browser.clone(),
+            force_ip_version: config.force_ip_version,
+            source_address: config.source_address.clone(),
+            socket_timeout: config.socket_timeout_secs,
+            sponsorblock_remove: config.sponsorblock_remove.clone(),
+            extra_args: config.ytdlp_extra_args.clone(),
+            audio_only,
+            audio_format: config.audio_format.clone(),
+            audio_quality: config.audio_quality.clone(),
+            clip_range: None,
+            max_filename_length: config.max_filename_length,
+            resume: false,
+            split_chapters: config.split_chapters,
+        }
+    }
+}
+
+/// The file yt-dlp downloads to before renaming it to `path`
+pub fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.to_path_buf().into_os_string();
+    partial.push(".part");
+    PathBuf::from(partial)
 }
 
 pub async fn download_video(
     job_id: Uuid,
     url: String,
-    quality: String,
-    output_dir: PathBuf,
+    options: DownloadOptions,
     update_tx: mpsc::UnboundedSender<(Uuid, JobUpdate)>,
-) -> Result<(String, PathBuf)> {
+) -> Result<(String, PathBuf, Option<u64>)> {
+    let output_dir = &options.output_dir;
+
     // Create temp directory for downloads
     let temp_dir = output_dir.join(".temp");
     tokio::fs::create_dir_all(&temp_dir).await?;
 
     // Build output template
-    let output_template = temp_dir.join("%(title)s.%(ext)s");
-
-    // Build quality format string
-    // Use merge-output-format to ensure video and audio are properly merged
-    let format = match quality.as_str() {
-        "best" => "bestvideo+bestaudio/best",
-        "1080p" => "bestvideo[height<=1080]+bestaudio/best[height<=1080]",
-        "720p" => "bestvideo[height<=720]+bestaudio/best[height<=720]",
-        "480p" => "bestvideo[height<=480]+bestaudio/best[height<=480]",
-        _ => "bestvideo+bestaudio/best",
-    };
+    let output_template = temp_dir.join(&options.output_template);
 
-    // Spawn yt-dlp process
-    let mut child = Command::new("yt-dlp")
-        .arg("-f")
-        .arg(format)
-        .arg("--merge-output-format")
-        .arg("mp4")
+    // Thumbnails are named after the job so they can be found afterwards
+    // and don't get picked up as the downloaded video
+    let thumbnail_dir = output_dir.join(".thumbnails");
+    let thumbnail_path = thumbnail_dir.join(format!("{}.png", job_id));
+
+    // yt-dlp writes the duration and height of the selected format and the
+    // title here. The duration saves probing the file again before conversion
+    let metadata_path = std::env::temp_dir().join(format!("carbon-{}.metadata", job_id));
+    // And the path of the final file once all postprocessing is done
+    let filepath_path = std::env::temp_dir().join(format!("carbon-{}.filepath", job_id));
+
+    // Build yt-dlp command
+    let mut command = Command::new(&options.command);
+    add_source_args(&mut command, &options);
+    command
         .arg("--newline")
         .arg("--no-playlist")
         .arg("-o")
         .arg(output_template.to_string_lossy().to_string())
+        .arg("--print-to-file")
+        .arg("%(duration)s %(height)s %(title)s")
+        .arg(&metadata_path)
+        .arg("--print-to-file")
+        .arg("after_move:%(filepath)s")
+        .arg(&filepath_path);
+
+    if let Some(max_length) = options.max_filename_length {
+        command.arg("--trim-filenames").arg(max_length.to_string());
+    }
+    // Keeps the .part file and requests only the missing bytes. Writing
+    // without one (--no-part) would make a partial file look finished
+    if options.resume {
+        command.arg("--continue");
+    }
+    if let Some((start, end)) = &options.clip_range {
+        command
+            .arg("--download-sections")
+            .arg(format!("*{}-{}", start, end));
+    }
+    if let Some(rate_limit) = &options.rate_limit {
+        command.arg("--limit-rate").arg(rate_limit);
+    }
+    // yt-dlp still reports the progress of external downloaders in its own
+    // format, so it's parsed the same way
+    if let Some(downloader) = &options.external_downloader {
+        command.arg("--downloader").arg(downloader);
+    }
+    if !options.sponsorblock_remove.is_empty() {
+        command
+            .arg("--sponsorblock-remove")
+            .arg(options.sponsorblock_remove.join(","));
+    }
+
+    // Videos without a thumbnail only make yt-dlp print a warning. The
+    // thumbnail is embedded after merging, so the mp4 stays playable
+    if options.embed_thumbnail {
+        command.arg("--embed-thumbnail");
+    }
+    if options.embed_metadata {
+        command.arg("--embed-metadata");
+    }
+    // Chapters get a directory of their own, so they're told apart from the
+    // full video, which is kept
+    let chapter_dir = temp_dir.join(format!("chapters-{}", job_id));
+    if options.split_chapters {
+        command.arg("--split-chapters").arg("-o").arg(format!(
+            "chapter:{}",
+            chapter_dir
+                .join("%(title)s - %(section_number)02d %(section_title)s.%(ext)s")
+                .to_string_lossy()
+        ));
+    }
+    if options.write_thumbnail {
+        tokio::fs::create_dir_all(&thumbnail_dir).await?;
+        command
+            .arg("--write-thumbnail")
+            .arg("--convert-thumbnails")
+            .arg("png")
+            .arg("-o")
+            .arg(format!(
+                "thumbnail:{}",
+                thumbnail_dir
+                    .join(format!("{}.%(ext)s", job_id))
+                    .to_string_lossy()
+            ));
+    }
+
+    // Spawn yt-dlp process. Extra arguments go last so they can override
+    // carbon's choices, like the format
+    command
+        .args(&options.extra_args)
         .arg(&url)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .spawn()?;
+        .kill_on_drop(true);
+    tracing::debug!(job = %job_id, command = ?command.as_std(), "running downloader");
+    let mut child = command.spawn()?;
 
     let stdout = child.stdout.take().expect("Failed to capture stdout");
     let stderr = child.stderr.take().expect("Failed to capture stderr");
@@ -63,18 +232,36 @@ pub async fn download_video(
     let progress_regex = Regex::new(r"\[download\]\s+(\d+\.?\d*)%")?;
     let speed_regex = Regex::new(r"at\s+(\S+/s)")?;
     let eta_regex = Regex::new(r"ETA\s+(\S+)")?;
+    // Fragmented downloads only have an estimate, marked with "~"
+    let size_regex = Regex::new(r"%\s+of\s+~?\s*(\d+(?:\.\d+)?\w+)")?;
     let destination_regex = Regex::new(r"\[download\] Destination: (.+)")?;
+    // Steps yt-dlp takes once the download is done, the rest is
+    // "post-processing"
+    let postprocess_regex = Regex::new(
+        r"^\[(Merger|ExtractAudio|ffmpeg|FFmpeg\w*|Fixup\w*|EmbedThumbnail|Metadata|ModifyChapters|SponsorBlock|SplitChapters)\]",
+    )?;
 
     let mut title: Option<String> = None;
-    let mut output_path: Option<PathBuf> = None;
 
     // Read output in background
     let update_tx_clone = update_tx.clone();
     let job_id_clone = job_id;
     tokio::spawn(async move {
+        // Merged downloads fetch one format after another. Those done count
+        // in full, the current one as far as it got
+        let mut done_bytes = 0u64;
+        let mut current_size: Option<u64> = None;
+        let mut current_percent = 0.0;
+        let mut started_at = None;
+        let current_bytes = |size: Option<u64>, percent: f64| {
+            size.map_or(0, |size| (size as f64 * percent / 100.0) as u64)
+        };
+
         while let Ok(Some(line)) = stdout_reader.next_line().await {
             if let Some(caps) = progress_regex.captures(&line) {
                 if let Ok(percent) = caps[1].parse::<f64>() {
+                    started_at.get_or_insert_with(Instant::now);
+                    current_percent = percent;
                     let _ = update_tx_clone.send((job_id_clone, JobUpdate::Progress(percent)));
                 }
             }
@@ -89,10 +276,40 @@ pub async fn download_video(
                 let _ = update_tx_clone.send((job_id_clone, JobUpdate::Eta(eta)));
             }
 
+            if let Some(caps) = size_regex.captures(&line) {
+                let size = caps[1].to_string();
+                current_size = parse_size(&size);
+                let _ = update_tx_clone.send((job_id_clone, JobUpdate::Size(size)));
+            }
+
             if let Some(caps) = destination_regex.captures(&line) {
+                done_bytes += current_bytes(current_size.take(), current_percent);
+                current_percent = 0.0;
                 let path = PathBuf::from(&caps[1]);
                 let _ = update_tx_clone.send((job_id_clone, JobUpdate::TempPath(path)));
             }
+
+            if let Some(caps) = postprocess_regex.captures(&line) {
+                let step = match &caps[1] {
+                    "Merger" => "merging",
+                    "ExtractAudio" => "extracting audio",
+                    "EmbedThumbnail" => "embedding thumbnail",
+                    "Metadata" => "adding metadata",
+                    "ModifyChapters" | "SponsorBlock" => "cutting segments",
+                    "SplitChapters" => "splitting chapters",
+                    _ => "post-processing",
+                };
+                let _ = update_tx_clone.send((job_id_clone, JobUpdate::Postprocess(step.into())));
+            }
+        }
+
+        // Failed attempts count too, their bytes were transferred all the same
+        if let Some(started_at) = started_at {
+            let bytes = done_bytes + current_bytes(current_size, current_percent);
+            let _ = update_tx_clone.send((
+                job_id_clone,
+                JobUpdate::Downloaded(bytes, started_at.elapsed()),
+            ));
         }
     });
 
@@ -112,41 +329,406 @@ pub async fn download_video(
     let status = child.wait().await?;
 
     if !status.success() {
-        let error_msg = stderr_output.join("\n");
-        return Err(color_eyre::eyre::eyre!("yt-dlp failed: {}", error_msg));
+        let mut error_msg = stderr_output.join("\n");
+        if let Some(hint) = error_hint(&error_msg) {
+            error_msg.push_str(&format!("\nhint: {}", hint));
+        }
+        return Err(color_eyre::eyre::eyre!(
+            "{} failed: {}",
+            options.command.display(),
+            error_msg
+        ));
     }
 
-    // Find the downloaded file
-    let mut entries = tokio::fs::read_dir(&temp_dir).await?;
-    while let Some(entry) = entries.next_entry().await? {
-        let path = entry.path();
-        if path.is_file() {
+    // Find the downloaded file, preferring the path yt-dlp reported
+    let mut output_path = tokio::fs::read_to_string(&filepath_path)
+        .await
+        .ok()
+        .map(|contents| PathBuf::from(contents.trim()))
+        .filter(|path| path.is_file());
+    let _ = tokio::fs::remove_file(&filepath_path).await;
+
+    if output_path.is_none() {
+        let mut entries = tokio::fs::read_dir(&temp_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            // Audio extraction should only leave the audio file behind, but
+            // make sure to never pick up anything else
+            let matches_format = !options.audio_only
+                || path.extension().and_then(|e| e.to_str()) == Some(&options.audio_format);
+            if path.is_file() && matches_format {
+                output_path = Some(path);
+                break;
+            }
+        }
+    }
+
+    // Fields are "NA" when the extractor doesn't provide them. The title
+    // comes last as it may contain spaces
+    let metadata = tokio::fs::read_to_string(&metadata_path)
+        .await
+        .unwrap_or_default();
+    let _ = tokio::fs::remove_file(&metadata_path).await;
+    let mut fields = metadata.trim_end().splitn(3, ' ');
+    let duration_field = fields.next();
+    let height_field = fields.next();
+    // The file name may be trimmed or have characters replaced, the title
+    // is kept as it is
+    if let Some(video_title) = fields.next().filter(|t| !t.is_empty() && *t != "NA") {
+        title = Some(video_title.to_string());
+    }
+
+    if let Some(path) = &output_path {
+        if title.is_none() {
             let file_name = path
                 .file_stem()
                 .and_then(|s| s.to_str())
                 .unwrap_or("video")
                 .to_string();
-
-            if title.is_none() {
-                title = Some(file_name.clone());
-                let _ = update_tx.send((job_id, JobUpdate::Title(file_name)));
-            }
-
-            output_path = Some(path);
-            break;
+            title = Some(file_name.clone());
+            let _ = update_tx.send((job_id, JobUpdate::Title(file_name)));
         }
     }
 
     let output_path =
         output_path.ok_or_else(|| color_eyre::eyre::eyre!("Downloaded file not found"))?;
+
+    // Videos without chapters are only downloaded
+    if options.split_chapters {
+        let chapters = collect_chapters(&chapter_dir, &temp_dir).await;
+        if !chapters.is_empty() {
+            let _ = update_tx.send((job_id, JobUpdate::Chapters(chapters)));
+        }
+    }
+
+    // A missing thumbnail is not an error, some sources simply don't have one
+    if options.write_thumbnail && thumbnail_path.exists() {
+        let _ = update_tx.send((job_id, JobUpdate::ThumbnailPath(thumbnail_path)));
+    }
     let title = title.unwrap_or_else(|| "Unknown".to_string());
 
-    Ok((title, output_path))
+    // The reported duration is the full video's, clips are probed instead
+    let duration = duration_field
+        .and_then(|field| field.parse::<f64>().ok())
+        .map(|seconds| seconds as u64)
+        .filter(|_| options.clip_range.is_none());
+    if let Some(duration) = duration {
+        let _ = update_tx.send((job_id, JobUpdate::Duration(duration)));
+    }
+
+    if let Some(height) = height_field.and_then(|field| field.parse::<u32>().ok()) {
+        let _ = update_tx.send((job_id, JobUpdate::Height(height)));
+    }
+
+    Ok((title, output_path, duration))
+}
+
+/// Add the arguments choosing what yt-dlp fetches and how it gets access:
+/// the format, headers, cookies and network
+fn add_source_args(command: &mut Command, options: &DownloadOptions) {
+    if options.audio_only {
+        command
+            .arg("-f")
+            .arg(options.format.as_deref().unwrap_or("bestaudio/best"))
+            .arg("-x")
+            .arg("--audio-format")
+            .arg(&options.audio_format)
+            .arg("--audio-quality")
+            .arg(&options.audio_quality);
+    } else {
+        // Build quality format string, unless an exact format was asked for
+        // Use merge-output-format to ensure video and audio are properly merged
+        let format = options
+            .format
+            .clone()
+            .unwrap_or_else(|| format_selector(&options.quality, &options.format_fallbacks));
+        command
+            .arg("-f")
+            .arg(format)
+            .arg("--merge-output-format")
+            .arg("mp4");
+    }
+
+    if let Some(user_agent) = &options.user_agent {
+        command.arg("--user-agent").arg(user_agent);
+    }
+    if let Some(referer) = &options.referer {
+        command.arg("--referer").arg(referer);
+    }
+    // A cookies file is more specific than a whole browser profile
+    if let Some(cookies_file) = &options.cookies_file {
+        command.arg("--cookies").arg(cookies_file);
+    } else if let Some(browser) = &options.cookies_from_browser {
+        command.arg("--cookies-from-browser").arg(browser);
+    }
+
+    // Validated to be 4 or 6
+    if let Some(version) = options.force_ip_version {
+        command.arg(format!("--force-ipv{}", version));
+    }
+    if let Some(address) = &options.source_address {
+        command.arg("--source-address").arg(address);
+    }
+    if let Some(timeout) = options.socket_timeout {
+        command.arg("--socket-timeout").arg(timeout.to_string());
+    }
+}
+
+/// What a download would fetch, as found by simulating it
+#[derive(Debug, Clone, Default)]
+pub struct Simulation {
+    pub title: Option<String>,
+    /// Size of the selected formats in bytes, estimated by some sites
+    pub size: Option<u64>,
+    pub duration: Option<u64>,
+    pub height: Option<u32>,
+}
+
+/// Let yt-dlp resolve a download with its options, checking the url and the
+/// format selection, without downloading anything
+pub async fn simulate_download(url: &str, options: &DownloadOptions) -> Result<Simulation> {
+    let mut command = Command::new(&options.command);
+    add_source_args(&mut command, options);
+    command
+        .arg("--simulate")
+        .arg("--no-playlist")
+        .arg("--print")
+        .arg("%(filesize,filesize_approx)s\t%(duration)s\t%(height)s\t%(title)s")
+        .args(&options.extra_args)
+        .arg(url)
+        .kill_on_drop(true);
+    tracing::debug!(command = ?command.as_std(), "running simulation");
+    let output = command.output().await?;
+
+    if !output.status.success() {
+        let mut error_msg = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if let Some(hint) = error_hint(&error_msg) {
+            error_msg.push_str(&format!("\nhint: {}", hint));
+        }
+        return Err(color_eyre::eyre::eyre!(
+            "{} failed: {}",
+            options.command.display(),
+            error_msg
+        ));
+    }
+
+    // Fields are "NA" when the extractor doesn't provide them
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.trim_end().splitn(4, '\t');
+    let mut next = || fields.next().filter(|field| *field != "NA");
+    Ok(Simulation {
+        size: next()
+            .and_then(|field| field.parse::<f64>().ok())
+            .map(|bytes| bytes as u64),
+        duration: next()
+            .and_then(|field| field.parse::<f64>().ok())
+            .map(|seconds| seconds as u64),
+        height: next().and_then(|field| field.parse().ok()),
+        title: next().filter(|title| !title.is_empty()).map(str::to_string),
+    })
+}
+
+/// Move the chapters split from a video into the temp directory, in order,
+/// and remove the directory they were written to
+async fn collect_chapters(chapter_dir: &Path, temp_dir: &Path) -> Vec<PathBuf> {
+    let mut chapters = Vec::new();
+    if let Ok(mut entries) = tokio::fs::read_dir(chapter_dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let destination = temp_dir.join(entry.file_name());
+            if tokio::fs::rename(entry.path(), &destination).await.is_ok() {
+                chapters.push(destination);
+            }
+        }
+    }
+    let _ = tokio::fs::remove_dir(chapter_dir).await;
+
+    // Names contain the zero padded chapter number
+    chapters.sort();
+    chapters
+}
+
+/// Build the yt-dlp format selector for a quality preset. Height limited
+/// presets fall back to a single file of that height and finally to any
+/// format, so sources without matching video-only streams still download.
+/// Configured fallbacks replace this chain, without the ones needing a
+/// height for qualities that have none.
+fn format_selector(quality: &str, fallbacks: &[String]) -> String {
+    let height = quality
+        .strip_suffix('p')
+        .and_then(|h| h.parse::<u32>().ok());
+
+    let chain: Vec<String> = fallbacks
+        .iter()
+        .filter_map(|selector| match height {
+            Some(height) => Some(selector.replace("{height}", &height.to_string())),
+            None => (!selector.contains("{height}")).then(|| selector.clone()),
+        })
+        .collect();
+    if !chain.is_empty() {
+        return chain.join("/");
+    }
+
+    match height {
+        Some(height) => format!(
+            "bestvideo[height<={0}]+bestaudio/best[height<={0}]/best",
+            height
+        ),
+        None => "bestvideo+bestaudio/best".to_string(),
+    }
+}
+
+/// Suggest a fix for well-known yt-dlp failures
+fn error_hint(stderr: &str) -> Option<&'static str> {
+    if stderr.contains("HTTP Error 403") {
+        Some("the site refused the request, try setting user_agent or referer in the config")
+    } else {
+        None
+    }
+}
+
+/// Extract the host of a URL, without port or leading "www."
+pub fn url_host(url: &str) -> Option<&str> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+
+    if host.is_empty() {
+        None
+    } else {
+        Some(host)
+    }
+}
+
+/// Bytes per second of a rate limit like "500K" or "2M", with binary
+/// suffixes as yt-dlp reads them
+pub fn parse_rate_limit(limit: &str) -> Option<u64> {
+    let (number, multiplier) = match limit.chars().last()? {
+        'K' | 'k' => (&limit[..limit.len() - 1], 1024.0),
+        'M' | 'm' => (&limit[..limit.len() - 1], 1024.0 * 1024.0),
+        _ => (limit, 1.0),
+    };
+    let bytes = number.parse::<f64>().ok()? * multiplier;
+    (bytes.is_finite() && bytes >= 1.0).then_some(bytes as u64)
+}
+
+/// Whether a host is the site or one of its subdomains
+pub fn host_matches(host: &str, site: &str) -> bool {
+    host == site || host.ends_with(&format!(".{}", site))
+}
+
+/// Whether the input looks like an http(s) URL with a host. This is only a
+/// sanity check, yt-dlp decides what it can download.
+pub fn is_http_url(input: &str) -> bool {
+    let has_scheme = input.starts_with("http://") || input.starts_with("https://");
+    has_scheme
+        && !input.contains(char::is_whitespace)
+        && url_host(input).is_some_and(|host| host.contains('.') || host == "localhost")
+}
+
+/// Query parameters that don't change which video a link points to, mostly
+/// tracking where it was shared from. "t" is a start time.
+const IGNORED_PARAMS: [&str; 7] = ["si", "feature", "fbclid", "gclid", "igshid", "pp", "t"];
+
+/// Reduce a URL to what identifies the video, so that links shared in
+/// different ways compare equal: the scheme, "www.", the fragment, a trailing
+/// slash and parameters like "si", "t" or "utm_source" are dropped.
+pub fn normalize_url(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let rest = rest.split('#').next().unwrap_or(rest);
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let path = path.trim_end_matches('/');
+    // Only the host is case insensitive, ids in the path or query aren't
+    let (host, path) = path.split_once('/').unwrap_or((path, ""));
+    let host = host.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let address = if path.is_empty() {
+        host.to_string()
+    } else {
+        format!("{}/{}", host, path)
+    };
+
+    let params: Vec<&str> = query
+        .split('&')
+        .filter(|param| {
+            let name = param.split('=').next().unwrap_or_default();
+            !param.is_empty() && !name.starts_with("utm_") && !IGNORED_PARAMS.contains(&name)
+        })
+        .collect();
+
+    if params.is_empty() {
+        address
+    } else {
+        format!("{}?{}", address, params.join("&"))
+    }
+}
+
+/// List the entries of a playlist without downloading anything. A single
+/// video yields a single entry.
+pub async fn expand_playlist(ytdlp: &Path, url: &str) -> Result<Vec<PlaylistEntry>> {
+    let output = Command::new(ytdlp)
+        .arg("--flat-playlist")
+        .arg("--print")
+        .arg("%(webpage_url,url)s\t%(title)s")
+        .arg(url)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(color_eyre::eyre::eyre!("Failed to expand playlist"));
+    }
+
+    let entries = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (url, title) = line.split_once('\t')?;
+            Some(PlaylistEntry {
+                url: url.to_string(),
+                title: (title != "NA").then(|| title.to_string()),
+            })
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// List the formats yt-dlp can download a video in, as `yt-dlp -F` shows
+/// them
+pub async fn list_formats(ytdlp: &Path, url: &str) -> Result<Vec<FormatEntry>> {
+    let output = Command::new(ytdlp)
+        .arg("--list-formats")
+        .arg("--no-playlist")
+        .arg(url)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let error = stderr.lines().last().unwrap_or("yt-dlp failed");
+        return Err(color_eyre::eyre::eyre!("{}", error.trim()));
+    }
+
+    // A table with a line of dashes below the header, the first column is
+    // the format code
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let header = stdout.lines().position(|line| line.starts_with("---"));
+    let formats = stdout
+        .lines()
+        .skip(header.map_or(usize::MAX, |i| i + 1))
+        .filter_map(|line| {
+            let id = line.split_whitespace().next()?;
+            Some(FormatEntry {
+                id: id.to_string(),
+                description: line.trim_end().to_string(),
+            })
+        })
+        .collect();
+    Ok(formats)
 }
 
 // Function to get video info without downloading
-pub async fn get_video_info(url: &str) -> Result<String> {
-    let output = Command::new("yt-dlp")
+pub async fn get_video_info(ytdlp: &Path, url: &str) -> Result<String> {
+    let output = Command::new(ytdlp)
         .arg("--get-title")
         .arg("--no-playlist")
         .arg(url)
@@ -159,3 +741,62 @@ pub async fn get_video_info(url: &str) -> Result<String> {
         Err(color_eyre::eyre::eyre!("Failed to get video info"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_url_drops_scheme_and_www() {
+        assert_eq!(
+            normalize_url("https://www.youtube.com/watch?v=abc"),
+            "youtube.com/watch?v=abc"
+        );
+        assert_eq!(
+            normalize_url("http://youtube.com/watch?v=abc"),
+            "youtube.com/watch?v=abc"
+        );
+    }
+
+    #[test]
+    fn normalize_url_lowercases_only_the_host() {
+        assert_eq!(normalize_url("https://WWW.Example.com"), "example.com");
+        assert_eq!(
+            normalize_url("https://WWW.Example.com"),
+            normalize_url("example.com")
+        );
+        assert_eq!(
+            normalize_url("https://YouTu.be/AbC?X=Y"),
+            "youtu.be/AbC?X=Y"
+        );
+    }
+
+    #[test]
+    fn normalize_url_drops_tracking_params() {
+        assert_eq!(
+            normalize_url("https://youtu.be/abc?si=xyz&t=42"),
+            "youtu.be/abc"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/v?utm_source=a&id=1&utm_medium=b&pp=c"),
+            "example.com/v?id=1"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/v?fbclid=1&gclid=2&igshid=3&feature=share"),
+            "example.com/v"
+        );
+    }
+
+    #[test]
+    fn normalize_url_drops_fragment_and_trailing_slash() {
+        assert_eq!(
+            normalize_url("https://example.com/video/#comments"),
+            "example.com/video"
+        );
+        assert_eq!(normalize_url("https://example.com/"), "example.com");
+        assert_eq!(
+            normalize_url("https://example.com/v?id=1#t=10"),
+            "example.com/v?id=1"
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use crate::history::{json_string, utc_timestamp};
+use crate::models::Job;
+use color_eyre::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Write the id, url, title, status, output path and error of `jobs` to a
+/// timestamped file in `dir`, as "json" or "csv"
+pub fn export_jobs(jobs: &[Job], dir: &Path, format: &str) -> Result<PathBuf> {
+    // Colons aren't allowed in file names on Windows
+    let timestamp = utc_timestamp(SystemTime::now()).replace(':', "-");
+    let path = dir.join(format!("carbon-jobs-{}.{}", timestamp, format));
+
+    let contents = if format == "csv" {
+        jobs_csv(jobs)
+    } else {
+        jobs_json(jobs)
+    };
+    fs::create_dir_all(dir)?;
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+/// The fields of a job in export order, unset ones as None
+fn job_fields(job: &Job) -> [Option<String>; 6] {
+    [
+        Some(job.id.to_string()),
+        Some(job.url.clone()),
+        Some(job.display_title()),
+        Some(job.status().label().to_string()),
+        job.output_path
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string()),
+        job.error().cloned(),
+    ]
+}
+
+const FIELD_NAMES: [&str; 6] = ["id", "url", "title", "status", "output_path", "error"];
+
+/// An array of objects, one per job, with unset fields as null
+fn jobs_json(jobs: &[Job]) -> String {
+    let objects: Vec<String> = jobs
+        .iter()
+        .map(|job| {
+            let fields: Vec<String> = FIELD_NAMES
+                .iter()
+                .zip(job_fields(job))
+                .map(|(name, value)| {
+                    let value = value.as_deref().map_or("null".to_string(), json_string);
+                    format!("\"{}\":{}", name, value)
+                })
+                .collect();
+            format!("  {{{}}}", fields.join(","))
+        })
+        .collect();
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
+
+/// A header row and a row per job, with unset fields left empty
+fn jobs_csv(jobs: &[Job]) -> String {
+    let mut csv = FIELD_NAMES.join(",") + "\n";
+    for job in jobs {
+        let row: Vec<String> = job_fields(job)
+            .iter()
+            .map(|value| csv_field(value.as_deref().unwrap_or("")))
+            .collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,89 @@
+use crate::config::config_path;
+use crate::models::Job;
+use color_eyre::Result;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Held while appending, so entries of jobs completing together can't mix
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn history_path() -> Result<PathBuf> {
+    Ok(config_path()?.with_file_name("history.jsonl"))
+}
+
+/// Append a completed job to the download history, one JSON object per line
+pub fn append_history(job: &Job) -> Result<()> {
+    let output = job
+        .output_path
+        .as_ref()
+        .map(|path| json_string(&path.to_string_lossy()))
+        .unwrap_or_else(|| "null".to_string());
+    let line = format!(
+        "{{\"timestamp\":{},\"title\":{},\"url\":{},\"output_path\":{}}}\n",
+        json_string(&utc_timestamp(SystemTime::now())),
+        json_string(&job.display_title()),
+        json_string(&job.url),
+        output,
+    );
+
+    let _guard = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path()?)?;
+    // A single write, so a crash can't leave half a line behind
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Quote and escape a string for JSON
+pub fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Format a time as RFC 3339 in UTC, e.g. "2024-05-01T12:34:56Z"
+pub fn utc_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01, Howard Hinnant's civil_from_days
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
@@ -0,0 +1,34 @@
+use crate::config::config_path;
+use color_eyre::Result;
+use std::fs::{self, OpenOptions};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
+
+/// Size above which the log is moved aside at startup, keeping one old log
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+
+pub fn log_path() -> Result<PathBuf> {
+    Ok(config_path()?.with_file_name("carbon.log"))
+}
+
+/// Log to carbon.log next to the config at `level`, e.g. "info" or "debug".
+/// Never to stdout or stderr, which belong to the UI.
+pub fn init(level: &str) -> Result<()> {
+    let level: LevelFilter = level.parse().unwrap_or(LevelFilter::INFO);
+    if level == LevelFilter::OFF {
+        return Ok(());
+    }
+
+    let path = log_path()?;
+    if fs::metadata(&path).is_ok_and(|metadata| metadata.len() > MAX_LOG_SIZE) {
+        fs::rename(&path, path.with_extension("log.old"))?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(Mutex::new(file))
+        .try_init()
+        .map_err(|e| color_eyre::eyre::eyre!("Could not start logging: {}", e))
+}
@@ -1,14 +1,27 @@
 mod app;
 mod config;
 mod converter;
+mod deps;
 mod downloader;
+mod export;
+mod history;
+mod logging;
 mod models;
+mod notify;
 mod queue;
+mod theme;
+mod thumbnail;
 mod ui;
+mod webhook;
 
 use app::App;
 use color_eyre::Result;
 
+/// xterm escapes saving and restoring the window title on a stack,
+/// terminals without them ignore them
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Install color-eyre for better error reporting
@@ -17,14 +30,39 @@ async fn main() -> Result<()> {
     // Load configuration
     let config = config::load_config()?;
 
-    // Initialize terminal
+    // The log only helps with bug reports, carbon runs fine without it
+    let _ = logging::init(&config.log_level);
+    tracing::info!("carbon {} starting", env!("CARGO_PKG_VERSION"));
+
+    // Look for yt-dlp and ffmpeg before anything tries to run them
+    let dependencies = deps::check_dependencies(&config).await;
+    tracing::info!(
+        ytdlp = ?dependencies.ytdlp,
+        ffmpeg = ?dependencies.ffmpeg,
+        "found dependencies"
+    );
+
+    // Initialize terminal, with mouse events for clicking and scrolling
     let mut terminal = ratatui::init();
+    // Also save the terminal title, carbon sets its own
+    crossterm::execute!(
+        std::io::stdout(),
+        crossterm::event::EnableMouseCapture,
+        crossterm::style::Print(PUSH_TITLE)
+    )?;
 
     // Create and run app
-    let mut app = App::new(config);
+    let mut app = App::new(config, dependencies);
     let result = app.run(&mut terminal).await;
 
-    // Restore terminal
+    // Restore terminal, and its title where the terminal can. Elsewhere the
+    // title is at least cleared
+    let _ = crossterm::execute!(
+        std::io::stdout(),
+        crossterm::event::DisableMouseCapture,
+        crossterm::terminal::SetTitle(""),
+        crossterm::style::Print(POP_TITLE)
+    );
     ratatui::restore();
 
     result
@@ -1,19 +1,121 @@
+use crate::deps::Dependencies;
+use crate::downloader::{host_matches, normalize_url, url_host};
+use crate::theme::Theme;
+use image::RgbImage;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+/// How long status messages stay visible
+const STATUS_MESSAGE_DURATION: Duration = Duration::from_secs(3);
+
+/// How long a second `d` has to delete the job the first one marked
+const DELETE_CONFIRM_WINDOW: Duration = Duration::from_secs(3);
+
+/// How many of the latest completions the batch eta averages over
+const RECENT_COMPLETIONS: usize = 10;
+
+/// Start and end of the part of a video to download, as given by the user
+pub type ClipRange = (String, String);
+
+/// Upper limit for max_concurrent_downloads
+pub const MAX_CONCURRENT_DOWNLOADS: usize = 10;
+
+/// Quality presets, from highest to lowest
+pub const QUALITY_PRESETS: [&str; 4] = ["best", "1080p", "720p", "480p"];
+
+/// Codec and container used when converting downloads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConversionProfile {
+    /// H.264 with PCM audio in mp4
+    #[default]
+    #[serde(rename = "davinci_h264")]
+    DaVinciH264,
+    /// DNxHR HQ in mov
+    #[serde(rename = "dnxhd")]
+    DNxHD,
+    /// ProRes 422 HQ in mov
+    #[serde(rename = "prores")]
+    ProRes,
+}
+
+impl ConversionProfile {
+    /// Suffix appended to the converted file's name
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            ConversionProfile::DaVinciH264 => "_davinci",
+            ConversionProfile::DNxHD => "_dnxhd",
+            ConversionProfile::ProRes => "_prores",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConversionProfile::DaVinciH264 => "mp4",
+            ConversionProfile::DNxHD | ConversionProfile::ProRes => "mov",
+        }
+    }
+}
+
+/// Glyphs progress bars are drawn with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProgressBarStyle {
+    /// Full and light shade blocks
+    #[default]
+    #[serde(rename = "blocks")]
+    Blocks,
+    /// "#" and "-", for terminals and fonts without block characters
+    #[serde(rename = "ascii")]
+    Ascii,
+    /// Full and bottom row braille dots
+    #[serde(rename = "braille")]
+    Braille,
+}
+
+impl ProgressBarStyle {
+    /// Glyphs of the done and remaining part
+    pub fn glyphs(&self) -> (&'static str, &'static str) {
+        match self {
+            ProgressBarStyle::Blocks => ("█", "░"),
+            ProgressBarStyle::Ascii => ("#", "-"),
+            ProgressBarStyle::Braille => ("⣿", "⣀"),
+        }
+    }
+}
+
+/// Overall status of a job, derived from its stages
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum JobStatus {
     Queued,
     Downloading,
+    /// Downloaded, yt-dlp is merging or post-processing the file
+    Processing,
     Converting,
     Complete,
     Failed,
+    Cancelled,
 }
 
 impl JobStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Downloading => "downloading",
+            JobStatus::Processing => "processing",
+            JobStatus::Converting => "converting",
+            JobStatus::Complete => "complete",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
     pub fn is_active(&self) -> bool {
-        matches!(self, JobStatus::Downloading | JobStatus::Converting)
+        matches!(
+            self,
+            JobStatus::Downloading | JobStatus::Processing | JobStatus::Converting
+        )
     }
 
     pub fn is_complete(&self) -> bool {
@@ -25,18 +127,122 @@ impl JobStatus {
     }
 }
 
-#[derive(Debug, Clone)]
+/// The stages a job goes through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageKind {
+    Download,
+    Conversion,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StageStatus {
+    #[default]
+    Pending,
+    Running,
+    Complete,
+    Failed,
+    Cancelled,
+    /// The stage doesn't apply, e.g. conversion with auto_convert off
+    Skipped,
+}
+
+impl StageStatus {
+    /// Whether the stage no longer blocks the job from completing
+    pub fn is_done(&self) -> bool {
+        matches!(self, StageStatus::Complete | StageStatus::Skipped)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stage {
+    pub status: StageStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobStages {
+    pub download: Stage,
+    pub conversion: Stage,
+}
+
+impl JobStages {
+    /// The stage currently in progress
+    pub fn running(&self) -> Option<StageKind> {
+        if self.download.status == StageStatus::Running {
+            Some(StageKind::Download)
+        } else if self.conversion.status == StageStatus::Running {
+            Some(StageKind::Conversion)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: StageKind) -> &mut Stage {
+        match kind {
+            StageKind::Download => &mut self.download,
+            StageKind::Conversion => &mut self.conversion,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Job {
     pub id: Uuid,
     pub url: String,
     pub title: Option<String>,
-    pub status: JobStatus,
+    /// Quality for this job, falls back to the globally selected quality
+    pub quality: Option<String>,
+    /// Exact yt-dlp format code, e.g. "137+140", used instead of the quality
+    #[serde(default)]
+    pub format: Option<String>,
+    /// Only download this part of the video
+    #[serde(default)]
+    pub clip_range: Option<ClipRange>,
+    pub audio_only: bool,
+    /// Converts a file on disk instead of downloading, `url` is its path
+    #[serde(default)]
+    pub local: bool,
+    /// Only resolves what would be downloaded, without downloading it
+    #[serde(default)]
+    pub simulate: bool,
+    /// Set while the URL is probed for playlist entries
+    #[serde(skip)]
+    pub expanding_playlist: bool,
+    pub stages: JobStages,
     pub progress: f64,
+    #[serde(skip)]
     pub speed: Option<String>,
+    #[serde(skip)]
     pub eta: Option<String>,
-    pub error: Option<String>,
+    /// What yt-dlp does after downloading, e.g. "merging"
+    #[serde(skip)]
+    pub postprocess: Option<String>,
+    /// Total size of the download as yt-dlp reports it, e.g. "123.45MiB"
+    #[serde(skip)]
+    pub size: Option<String>,
+    /// Automatic download retries made so far
+    #[serde(skip)]
+    pub retries: u32,
+    /// Output directory that was unavailable when the job started, it's
+    /// retried once the directory is back
+    #[serde(skip)]
+    pub output_unavailable: Option<PathBuf>,
     pub output_path: Option<PathBuf>,
     pub temp_path: Option<PathBuf>,
+    pub thumbnail_path: Option<PathBuf>,
+    /// Video duration in seconds, as reported by yt-dlp
+    pub duration: Option<u64>,
+    /// Height of the format yt-dlp actually selected
+    pub height: Option<u32>,
+    /// When the job first became active, on a monotonic clock
+    #[serde(skip)]
+    pub started_at: Option<Instant>,
+    /// When the job completed, failed or was cancelled
+    #[serde(skip)]
+    pub finished_at: Option<Instant>,
+    /// When its download last reported anything, to notice stalled ones
+    #[serde(skip)]
+    pub last_progress_at: Option<Instant>,
 }
 
 impl Job {
@@ -45,21 +251,136 @@ impl Job {
             id: Uuid::new_v4(),
             url,
             title: None,
-            status: JobStatus::Queued,
+            quality: None,
+            format: None,
+            clip_range: None,
+            audio_only: false,
+            local: false,
+            simulate: false,
+            expanding_playlist: false,
+            stages: JobStages::default(),
             progress: 0.0,
             speed: None,
             eta: None,
-            error: None,
+            postprocess: None,
+            size: None,
+            retries: 0,
+            output_unavailable: None,
             output_path: None,
             temp_path: None,
+            thumbnail_path: None,
+            duration: None,
+            height: None,
+            started_at: None,
+            finished_at: None,
+            last_progress_at: None,
+        }
+    }
+
+    /// A job converting a file already on disk, skipping the download
+    pub fn from_local_file(path: PathBuf) -> Self {
+        let mut job = Job::new(path.to_string_lossy().to_string());
+        job.title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string());
+        job.local = true;
+        job.stages.download.status = StageStatus::Skipped;
+        job.temp_path = Some(path);
+        job
+    }
+
+    pub fn status(&self) -> JobStatus {
+        let JobStages {
+            download,
+            conversion,
+        } = &self.stages;
+
+        if download.status == StageStatus::Failed || conversion.status == StageStatus::Failed {
+            JobStatus::Failed
+        } else if download.status == StageStatus::Cancelled
+            || conversion.status == StageStatus::Cancelled
+        {
+            JobStatus::Cancelled
+        } else if download.status == StageStatus::Running && self.postprocess.is_some() {
+            JobStatus::Processing
+        } else if download.status == StageStatus::Running {
+            JobStatus::Downloading
+        } else if conversion.status == StageStatus::Running {
+            JobStatus::Converting
+        } else if download.status.is_done() && conversion.status.is_done() {
+            JobStatus::Complete
+        } else {
+            // Either stage may still be waiting for a free slot
+            JobStatus::Queued
+        }
+    }
+
+    /// Reset unfinished or failed work so it runs again, either after a
+    /// restart or when retrying
+    pub fn reset_for_retry(&mut self) {
+        // A finished download, or a local file, can be picked up again at conversion
+        let downloaded = self.stages.download.status.is_done()
+            && self.temp_path.as_ref().is_some_and(|p| p.exists());
+
+        if downloaded {
+            self.stages.conversion = Stage::default();
+        } else {
+            self.stages = JobStages::default();
+        }
+        self.progress = 0.0;
+        self.speed = None;
+        self.eta = None;
+        self.postprocess = None;
+        self.size = None;
+        self.retries = 0;
+        self.output_unavailable = None;
+        self.started_at = None;
+        self.finished_at = None;
+        self.last_progress_at = None;
+    }
+
+    /// Time spent on the job so far, or in total once it has finished
+    pub fn elapsed(&self) -> Option<Duration> {
+        let started_at = self.started_at?;
+        let until = self.finished_at.unwrap_or_else(Instant::now);
+        Some(until.saturating_duration_since(started_at))
+    }
+
+    /// Rough time until the running stage finishes, from yt-dlp's eta or
+    /// otherwise the progress made so far
+    pub fn remaining(&self) -> Option<Duration> {
+        if let Some(eta) = self.eta.as_deref().and_then(parse_eta) {
+            return Some(eta);
+        }
+        let progress = self.progress.clamp(0.0, 100.0);
+        if progress <= 0.0 {
+            return None;
         }
+        let elapsed = self.elapsed()?.as_secs_f64();
+        Some(Duration::from_secs_f64(
+            elapsed * (100.0 - progress) / progress,
+        ))
+    }
+
+    /// Error of the stage that failed, if any
+    pub fn error(&self) -> Option<&String> {
+        self.stages
+            .download
+            .error
+            .as_ref()
+            .or(self.stages.conversion.error.as_ref())
     }
 
     pub fn display_title(&self) -> String {
+        if self.expanding_playlist {
+            return "playlist (expanding...)".to_string();
+        }
+
         self.title.clone().unwrap_or_else(|| {
-            // Truncate URL for display
-            if self.url.len() > 40 {
-                format!("{}...", &self.url[..40])
+            // Truncate URL for display, by character as URLs may contain
+            // unescaped non-ASCII text
+            if self.url.chars().count() > 40 {
+                format!("{}...", self.url.chars().take(40).collect::<String>())
             } else {
                 self.url.clone()
             }
@@ -68,11 +389,217 @@ impl Job {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub output_directory: String,
     pub max_concurrent_downloads: usize,
     pub default_quality: String,
     pub auto_convert: bool,
+    /// Codec and container of converted files
+    pub conversion_profile: ConversionProfile,
+    /// Format selectors tried in order instead of the quality presets,
+    /// "{height}" is replaced with the height of the quality
+    pub format_fallbacks: Vec<String>,
+    /// yt-dlp output template for downloaded files, must contain "%(ext)s"
+    pub output_template: String,
+    /// Longest name of downloaded files, without the extension. Longer
+    /// titles are cut off
+    pub max_filename_length: Option<usize>,
+    /// Suffix of converted files, instead of the conversion profile's
+    pub converted_suffix: Option<String>,
+    /// Move downloads into the output directory next to their conversion,
+    /// instead of deleting them
+    pub keep_original: bool,
+    /// x264 preset for the H.264 profile, from "ultrafast" to "veryslow"
+    pub ffmpeg_preset: String,
+    /// x264 constant rate factor for the H.264 profile, 0-51 (lower is better)
+    pub ffmpeg_crf: u8,
+    /// Hardware H.264 encoder to use instead of x264: "nvenc", "videotoolbox" or "qsv"
+    pub hw_accel: Option<String>,
+    /// Video bitrate for the H.264 profile instead of crf, e.g. "8M". x264
+    /// encodes in two passes to hit it
+    pub target_bitrate: Option<String>,
+    /// Scale converted videos to this size, e.g. "1920x1080". -1 for either
+    /// side keeps the aspect ratio
+    pub target_resolution: Option<String>,
+    /// Convert videos to this frame rate, 1-240
+    pub target_fps: Option<f64>,
+    /// Even out the loudness of converted audio with ffmpeg's loudnorm filter
+    pub normalize_audio: bool,
+    /// Characters wide the progress bars of running jobs are at most
+    pub progress_bar_width: usize,
+    /// Glyphs progress bars are drawn with
+    pub progress_bar_style: ProgressBarStyle,
+    /// Show the video thumbnail as half-block art in the detail overlay
+    pub thumbnail_preview: bool,
+    /// Embed the thumbnail into downloaded files as cover art
+    pub embed_thumbnail: bool,
+    /// Embed title, artist, date etc. into downloaded files
+    pub embed_metadata: bool,
+    /// Expand playlist URLs into one job per entry
+    pub allow_playlists: bool,
+    /// Also save each chapter of a video as its own file, with a job each
+    pub split_chapters: bool,
+    /// Resolve what jobs would download without downloading anything
+    pub dry_run: bool,
+    /// Only download the audio track, skipping conversion
+    pub audio_only: bool,
+    /// Audio format for audio-only downloads (e.g. "mp3", "m4a", "opus")
+    pub audio_format: String,
+    /// yt-dlp's audio quality for audio-only downloads, 0 (best) to 10 or a
+    /// bitrate like "320K"
+    pub audio_quality: String,
+    /// User-Agent header sent by yt-dlp instead of its default
+    pub user_agent: Option<String>,
+    /// Referer header sent by yt-dlp
+    pub referer: Option<String>,
+    /// Netscape format cookies file for sites that need a login
+    pub cookies_file: Option<PathBuf>,
+    /// Browser to take cookies from instead, e.g. "firefox" or "chrome"
+    pub cookies_from_browser: Option<String>,
+    /// Connect over only IPv4 or IPv6, 4 or 6
+    pub force_ip_version: Option<u8>,
+    /// Local IP address to connect from, e.g. a VPN interface's
+    pub source_address: Option<String>,
+    /// Maximum download rate per download, e.g. "500K" or "2M"
+    pub rate_limit: Option<String>,
+    /// Maximum download rate of all downloads together, split between them
+    pub total_rate_limit: Option<String>,
+    /// SponsorBlock categories cut out of downloads, e.g. "sponsor" or "intro"
+    pub sponsorblock_remove: Vec<String>,
+    /// yt-dlp to run when it isn't on the PATH, e.g. in a virtualenv
+    pub ytdlp_path: Option<PathBuf>,
+    /// ffmpeg to run when it isn't on the PATH, ffprobe is expected next to it
+    pub ffmpeg_path: Option<PathBuf>,
+    /// Program run for downloads, yt-dlp or something that takes its arguments
+    pub downloader_command: String,
+    /// Program yt-dlp hands the actual download to, e.g. "aria2c"
+    pub external_downloader: Option<String>,
+    /// Extra arguments for yt-dlp, added right before the url
+    pub ytdlp_extra_args: Vec<String>,
+    /// Extra output arguments for ffmpeg, added right before the output file
+    pub ffmpeg_extra_args: Vec<String>,
+    /// Add jobs for urls that are already queued, running or complete
+    pub allow_duplicates: bool,
+    /// Look up the latest yt-dlp release on GitHub at startup
+    pub check_ytdlp_updates: bool,
+    /// Show a desktop notification when a job completes or fails
+    pub notifications: bool,
+    /// Submit what Ctrl+V pastes right away, a job per line
+    pub paste_submits: bool,
+    /// URL to POST completed and failed jobs to as JSON
+    pub webhook_url: Option<String>,
+    /// File format of job list exports, "json" or "csv"
+    pub export_format: String,
+    /// Least severe messages written to carbon.log, e.g. "info" or "off"
+    pub log_level: String,
+    /// Times a failed download is retried automatically before giving up
+    pub max_retries: u32,
+    /// Seconds a download may go without progress before it fails, 0 for
+    /// no limit
+    pub stall_timeout_secs: u64,
+    /// Seconds yt-dlp waits for a server to respond before giving up
+    pub socket_timeout_secs: Option<u64>,
+    /// Ring the terminal bell when all jobs are done
+    pub bell_on_batch_complete: bool,
+    /// Ring the terminal bell when a job completes
+    pub bell_on_complete: bool,
+    /// Ring the terminal bell when a job fails
+    pub bell_on_error: bool,
+    /// Milliseconds between redraws and queue checks
+    pub tick_rate_ms: u64,
+    /// Milliseconds to wait for input before checking for shutdown again
+    pub poll_rate_ms: u64,
+    /// Stop running jobs right away when quitting. Otherwise carbon waits
+    /// for them to finish first
+    pub kill_on_quit: bool,
+    /// Profile whose settings replace the ones above, a key of `profiles`
+    pub active_profile: Option<String>,
+    /// Directories for jobs by site or quality, the first matching rule wins
+    pub output_rules: Vec<OutputRule>,
+    /// Per-site header overrides, keyed by host (e.g. "vimeo.com")
+    pub site_headers: HashMap<String, HeaderOverride>,
+    /// Named sets of settings to switch between, keyed by name
+    pub profiles: HashMap<String, Profile>,
+    /// Colors of the UI as hex strings, unset ones keep the default
+    pub theme: ThemeConfig,
+}
+
+/// Hex colors like "#64a0c8" for each role of the UI palette
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub bg: Option<String>,
+    pub input_bg: Option<String>,
+    pub accent: Option<String>,
+    pub text: Option<String>,
+    pub dim: Option<String>,
+    pub placeholder: Option<String>,
+    pub yellow: Option<String>,
+    pub green: Option<String>,
+    pub red: Option<String>,
+    pub selection: Option<String>,
+}
+
+impl ThemeConfig {
+    /// Each color by its role name
+    pub fn colors_mut(&mut self) -> [(&'static str, &mut Option<String>); 10] {
+        [
+            ("bg", &mut self.bg),
+            ("input_bg", &mut self.input_bg),
+            ("accent", &mut self.accent),
+            ("text", &mut self.text),
+            ("dim", &mut self.dim),
+            ("placeholder", &mut self.placeholder),
+            ("yellow", &mut self.yellow),
+            ("green", &mut self.green),
+            ("red", &mut self.red),
+            ("selection", &mut self.selection),
+        ]
+    }
+}
+
+/// Settings bundled under a name, unset ones keep the value of the config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub quality: Option<String>,
+    pub auto_convert: Option<bool>,
+    pub conversion_profile: Option<ConversionProfile>,
+    pub ffmpeg_preset: Option<String>,
+    pub ffmpeg_crf: Option<u8>,
+}
+
+/// Where jobs from a site or at a quality are saved instead of the output
+/// directory. Conditions that are set must all match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputRule {
+    /// Host of the url, subdomains included, e.g. "vimeo.com"
+    pub site: Option<String>,
+    /// Quality of the job, e.g. "2160p"
+    pub quality: Option<String>,
+    /// Directory relative to the output directory, or an absolute path
+    pub directory: String,
+}
+
+impl OutputRule {
+    fn matches(&self, url: &str, quality: &str) -> bool {
+        let site_matches = self
+            .site
+            .as_deref()
+            .is_none_or(|site| url_host(url).is_some_and(|host| host_matches(host, site)));
+        let quality_matches = self.quality.as_deref().is_none_or(|q| q == quality);
+        site_matches && quality_matches
+    }
+}
+
+/// Headers overriding the global ones for a single site
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeaderOverride {
+    pub user_agent: Option<String>,
+    pub referer: Option<String>,
 }
 
 impl Default for Config {
@@ -85,10 +612,408 @@ impl Default for Config {
             max_concurrent_downloads: 3,
             default_quality: "best".to_string(),
             auto_convert: true,
+            conversion_profile: ConversionProfile::default(),
+            format_fallbacks: Vec::new(),
+            output_template: "%(title)s.%(ext)s".to_string(),
+            max_filename_length: None,
+            converted_suffix: None,
+            keep_original: false,
+            ffmpeg_preset: "fast".to_string(),
+            ffmpeg_crf: 18,
+            hw_accel: None,
+            target_bitrate: None,
+            target_resolution: None,
+            target_fps: None,
+            normalize_audio: false,
+            progress_bar_width: 30,
+            progress_bar_style: ProgressBarStyle::default(),
+            thumbnail_preview: false,
+            embed_thumbnail: false,
+            embed_metadata: false,
+            allow_playlists: false,
+            split_chapters: false,
+            dry_run: false,
+            audio_only: false,
+            audio_format: "mp3".to_string(),
+            audio_quality: "5".to_string(),
+            user_agent: None,
+            referer: None,
+            cookies_file: None,
+            cookies_from_browser: None,
+            force_ip_version: None,
+            source_address: None,
+            rate_limit: None,
+            total_rate_limit: None,
+            sponsorblock_remove: Vec::new(),
+            ytdlp_path: None,
+            ffmpeg_path: None,
+            downloader_command: "yt-dlp".to_string(),
+            external_downloader: None,
+            ytdlp_extra_args: Vec::new(),
+            ffmpeg_extra_args: Vec::new(),
+            allow_duplicates: false,
+            check_ytdlp_updates: false,
+            notifications: true,
+            paste_submits: false,
+            webhook_url: None,
+            export_format: "json".to_string(),
+            log_level: "info".to_string(),
+            max_retries: 3,
+            stall_timeout_secs: 120,
+            socket_timeout_secs: None,
+            bell_on_batch_complete: false,
+            bell_on_complete: false,
+            bell_on_error: false,
+            tick_rate_ms: 50,
+            poll_rate_ms: 100,
+            kill_on_quit: true,
+            active_profile: None,
+            output_rules: Vec::new(),
+            site_headers: HashMap::new(),
+            profiles: HashMap::new(),
+            theme: ThemeConfig::default(),
         }
     }
 }
 
+impl Config {
+    /// The config with the settings of the active profile applied
+    pub fn with_active_profile(&self) -> Config {
+        let mut config = self.clone();
+        let Some(profile) = self
+            .active_profile
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+        else {
+            return config;
+        };
+
+        if let Some(quality) = &profile.quality {
+            config.default_quality = quality.clone();
+        }
+        if let Some(auto_convert) = profile.auto_convert {
+            config.auto_convert = auto_convert;
+        }
+        if let Some(conversion_profile) = profile.conversion_profile {
+            config.conversion_profile = conversion_profile;
+        }
+        if let Some(preset) = &profile.ffmpeg_preset {
+            config.ffmpeg_preset = preset.clone();
+        }
+        if let Some(crf) = profile.ffmpeg_crf {
+            config.ffmpeg_crf = crf;
+        }
+        config
+    }
+
+    /// yt-dlp as configured, or else from the PATH
+    pub fn ytdlp_program(&self) -> PathBuf {
+        self.ytdlp_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("yt-dlp"))
+    }
+
+    /// ffmpeg as configured, or else from the PATH
+    pub fn ffmpeg_program(&self) -> PathBuf {
+        self.ffmpeg_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("ffmpeg"))
+    }
+
+    /// ffprobe next to a configured ffmpeg, or else from the PATH
+    pub fn ffprobe_program(&self) -> PathBuf {
+        match &self.ffmpeg_path {
+            Some(ffmpeg) => {
+                ffmpeg.with_file_name(format!("ffprobe{}", std::env::consts::EXE_SUFFIX))
+            }
+            None => PathBuf::from("ffprobe"),
+        }
+    }
+
+    /// Directory a job is saved in, by the first output rule it matches
+    pub fn output_dir_for(&self, url: &str, quality: &str) -> PathBuf {
+        let output_dir = PathBuf::from(&self.output_directory);
+        match self
+            .output_rules
+            .iter()
+            .find(|rule| rule.matches(url, quality))
+        {
+            Some(rule) => output_dir.join(&rule.directory),
+            None => output_dir,
+        }
+    }
+
+    /// Switch to the next profile in alphabetical order, after the last one
+    /// back to no profile
+    pub fn cycle_profile(&mut self) {
+        let mut names: Vec<&String> = self.profiles.keys().collect();
+        names.sort();
+
+        let next = match &self.active_profile {
+            None => names.first(),
+            Some(active) => names
+                .iter()
+                .position(|name| *name == active)
+                .and_then(|i| names.get(i + 1)),
+        };
+        self.active_profile = next.map(|name| name.to_string());
+    }
+}
+
+/// Overlays rendered on top of the main view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlay {
+    /// Everything about the selected job, `scroll` lines down for long errors
+    Details {
+        scroll: u16,
+    },
+    QualityPicker {
+        index: usize,
+        delete_original: bool,
+    },
+    /// Settings editor, `editing` while the selected value is typed in
+    Settings {
+        index: usize,
+        editing: bool,
+    },
+    /// List of all key bindings
+    Help,
+    /// Asks before quitting while jobs are running
+    ConfirmQuit,
+    /// Formats yt-dlp offers for the selected job, to download one exactly
+    Formats {
+        index: usize,
+    },
+    /// The job filter is being typed
+    Filter,
+    /// Browsers to read cookies from, `index` 0 for none
+    BrowserPicker {
+        index: usize,
+    },
+    /// How the batch that just finished went
+    BatchSummary {
+        completed: usize,
+        failed: usize,
+        elapsed: Duration,
+    },
+}
+
+/// Config fields that can be changed from the settings overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    OutputDirectory,
+    MaxConcurrentDownloads,
+    DefaultQuality,
+    AutoConvert,
+}
+
+impl Setting {
+    /// All settings, in the order they are listed
+    pub const ALL: [Setting; 4] = [
+        Setting::OutputDirectory,
+        Setting::MaxConcurrentDownloads,
+        Setting::DefaultQuality,
+        Setting::AutoConvert,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Setting::OutputDirectory => "output directory",
+            Setting::MaxConcurrentDownloads => "max downloads",
+            Setting::DefaultQuality => "default quality",
+            Setting::AutoConvert => "auto convert",
+        }
+    }
+
+    /// Whether the value is typed in, rather than cycled through with enter
+    pub fn is_text(&self) -> bool {
+        matches!(
+            self,
+            Setting::OutputDirectory | Setting::MaxConcurrentDownloads
+        )
+    }
+
+    /// Current value of the setting in `config`
+    pub fn value(&self, config: &Config) -> String {
+        match self {
+            Setting::OutputDirectory => config.output_directory.clone(),
+            Setting::MaxConcurrentDownloads => config.max_concurrent_downloads.to_string(),
+            Setting::DefaultQuality => config.default_quality.clone(),
+            Setting::AutoConvert => if config.auto_convert { "on" } else { "off" }.to_string(),
+        }
+    }
+
+    /// Set a typed in value, returning false if it isn't valid
+    pub fn set(&self, config: &mut Config, value: &str) -> bool {
+        match self {
+            Setting::OutputDirectory if !value.is_empty() => {
+                config.output_directory = value.to_string();
+                true
+            }
+            Setting::MaxConcurrentDownloads => match value.parse::<usize>() {
+                Ok(max @ 1..=MAX_CONCURRENT_DOWNLOADS) => {
+                    config.max_concurrent_downloads = max;
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Copy the value of the setting from one config to another
+    pub fn copy(&self, from: &Config, to: &mut Config) {
+        match self {
+            Setting::OutputDirectory => to.output_directory = from.output_directory.clone(),
+            Setting::MaxConcurrentDownloads => {
+                to.max_concurrent_downloads = from.max_concurrent_downloads
+            }
+            Setting::DefaultQuality => to.default_quality = from.default_quality.clone(),
+            Setting::AutoConvert => to.auto_convert = from.auto_convert,
+        }
+    }
+
+    /// Switch to the next value of a setting that isn't typed in
+    pub fn cycle(&self, config: &mut Config) {
+        match self {
+            Setting::DefaultQuality => {
+                let index = QUALITY_PRESETS
+                    .iter()
+                    .position(|q| *q == config.default_quality)
+                    .map_or(0, |i| (i + 1) % QUALITY_PRESETS.len());
+                config.default_quality = QUALITY_PRESETS[index].to_string();
+            }
+            Setting::AutoConvert => config.auto_convert = !config.auto_convert,
+            Setting::OutputDirectory | Setting::MaxConcurrentDownloads => {}
+        }
+    }
+}
+
+/// How important a status message is, sets its color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Confirms something happened
+    Info,
+    /// Something may not work as intended
+    Warn,
+    /// Something couldn't be done
+    Error,
+}
+
+/// How the jobs list is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListMode {
+    /// Progress bars under active jobs
+    Detailed,
+    /// The detailed view with the progress inline, one line per job
+    Compact,
+    /// One line per job, for monitoring large batches
+    Log,
+}
+
+/// Order of the jobs list. Only the view is sorted, jobs keep their place
+/// in `AppState::jobs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    /// In the order they were added
+    Added,
+    /// Running jobs first, then queued, failed, cancelled and complete
+    Status,
+    /// Alphabetically by title
+    Title,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Added => SortMode::Status,
+            SortMode::Status => SortMode::Title,
+            SortMode::Title => SortMode::Added,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortMode::Added => "added",
+            SortMode::Status => "status",
+            SortMode::Title => "title",
+        }
+    }
+}
+
+/// Average of some durations, None if there are none
+fn average(durations: &[Duration]) -> Option<Duration> {
+    (!durations.is_empty()).then(|| durations.iter().sum::<Duration>() / durations.len() as u32)
+}
+
+/// Position of a status when sorting by status
+fn status_rank(status: JobStatus) -> u8 {
+    match status {
+        JobStatus::Downloading | JobStatus::Processing | JobStatus::Converting => 0,
+        JobStatus::Queued => 1,
+        JobStatus::Failed => 2,
+        JobStatus::Cancelled => 3,
+        JobStatus::Complete => 4,
+    }
+}
+
+/// Parse an eta as yt-dlp prints it, e.g. "02:05" or "1:02:05". None for
+/// "Unknown" or anything else it can't read.
+pub fn parse_eta(eta: &str) -> Option<Duration> {
+    let mut secs = 0u64;
+    let mut parts = 0;
+    for part in eta.trim().split(':') {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        secs = secs * 60 + part.parse::<u64>().ok()?;
+        parts += 1;
+    }
+    (parts <= 3).then(|| Duration::from_secs(secs))
+}
+
+/// Parse a size as yt-dlp prints it, e.g. "123.45MiB", into bytes
+pub fn parse_size(size: &str) -> Option<u64> {
+    let split = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (amount, unit) = size.split_at(split);
+    let multiplier = match unit.trim() {
+        "" | "B" => 1u64,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        "TiB" => 1 << 40,
+        "KB" | "kB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        _ => return None,
+    };
+    Some((amount.parse::<f64>().ok()? * multiplier as f64) as u64)
+}
+
+/// Totals of what was downloaded, this session or since carbon was first
+/// used
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadStats {
+    /// Jobs completed
+    pub completed: u64,
+    pub bytes: u64,
+    /// Time spent downloading, for the average speed
+    #[serde(skip)]
+    pub download_time: Duration,
+}
+
+impl DownloadStats {
+    /// Average download speed in bytes per second
+    pub fn average_speed(&self) -> Option<u64> {
+        let secs = self.download_time.as_secs_f64();
+        (secs > 0.0).then(|| (self.bytes as f64 / secs) as u64)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub jobs: Vec<Job>,
@@ -96,16 +1021,195 @@ pub struct AppState {
     pub input_buffer: String,
     pub selected_quality: String,
     pub selected_index: usize,
+    pub list_mode: ListMode,
+    pub sort_mode: SortMode,
+    /// No new jobs are started while paused, running ones continue
+    pub paused: bool,
+    /// Quitting once the running jobs are done, nothing new is started
+    pub finishing: bool,
+    /// When jobs started running or waiting after the list was idle, so
+    /// their batch is summed up once, when all of them are done
+    pub batch_started_at: Option<Instant>,
+    /// Index of the first job shown, the list scrolls to keep the selected
+    /// job visible
+    pub list_offset: usize,
+    /// Screen rows of the listed jobs as (first row, height, job index), as
+    /// last rendered, to map clicks back to jobs
+    pub job_rows: Vec<(u16, u16, usize)>,
+    /// Frames rendered so far, drives animations
+    pub tick: usize,
+    pub overlay: Option<Overlay>,
+    /// Decoded thumbnail of the job shown in the detail overlay
+    pub preview: Option<RgbImage>,
+    /// Formats listed in the formats overlay, `None` while they're looked up
+    pub formats: Option<Vec<FormatEntry>>,
+    /// Browsers found when the browser picker was opened
+    pub installed_browsers: Vec<&'static str>,
+    /// Job marked by pressing `d` once, and when, deleted by pressing it again
+    pub pending_delete: Option<(Uuid, Instant)>,
+    /// Downloaded since carbon started
+    pub session_stats: DownloadStats,
+    /// Downloaded ever, saved in the config directory
+    pub lifetime_stats: DownloadStats,
+    /// Short lived message shown below the input box, with its severity and
+    /// when it disappears
+    pub status_message: Option<(String, Severity, Instant)>,
+    /// Value being typed into an overlay, kept apart from the URL input
+    pub edit_buffer: String,
+    /// Only jobs matching this are listed, see `matches_filter`
+    pub filter: String,
+    /// Versions of yt-dlp and ffmpeg found at startup
+    pub dependencies: Dependencies,
+    /// Colors of the UI, parsed from the config
+    pub theme: Theme,
 }
 
 impl AppState {
     pub fn new(config: Config) -> Self {
         Self {
             jobs: Vec::new(),
-            selected_quality: config.default_quality.clone(),
+            selected_quality: config.with_active_profile().default_quality,
+            theme: Theme::from_config(&config.theme),
             config,
             input_buffer: String::new(),
             selected_index: 0,
+            list_mode: ListMode::Detailed,
+            sort_mode: SortMode::Added,
+            paused: false,
+            finishing: false,
+            batch_started_at: None,
+            list_offset: 0,
+            job_rows: Vec::new(),
+            tick: 0,
+            overlay: None,
+            preview: None,
+            formats: None,
+            installed_browsers: Vec::new(),
+            pending_delete: None,
+            session_stats: DownloadStats::default(),
+            lifetime_stats: DownloadStats::default(),
+            status_message: None,
+            edit_buffer: String::new(),
+            filter: String::new(),
+            dependencies: Dependencies::default(),
+        }
+    }
+
+    /// Whether a required tool is missing, which blocks the whole app
+    pub fn missing_dependencies(&self) -> bool {
+        !self.dependencies.missing().is_empty()
+    }
+
+    /// Show a message below the input box for a few seconds
+    pub fn set_status(&mut self, severity: Severity, message: impl Into<String>) {
+        self.status_message = Some((
+            message.into(),
+            severity,
+            Instant::now() + STATUS_MESSAGE_DURATION,
+        ));
+    }
+
+    /// Whether the job was marked for deletion moments ago, so pressing `d`
+    /// again deletes it
+    pub fn is_pending_delete(&self, id: Uuid) -> bool {
+        self.pending_delete
+            .is_some_and(|(pending, at)| pending == id && at.elapsed() < DELETE_CONFIRM_WINDOW)
+    }
+
+    /// Remove the status message once its time is up
+    pub fn expire_status(&mut self) {
+        if self
+            .status_message
+            .as_ref()
+            .is_some_and(|(_, _, expires_at)| *expires_at <= Instant::now())
+        {
+            self.status_message = None;
+        }
+    }
+
+    /// The text input keys go to, the settings editor's or the filter's
+    /// while they're open
+    pub fn active_input(&mut self) -> &mut String {
+        match self.overlay {
+            Some(Overlay::Settings { editing: true, .. }) => &mut self.edit_buffer,
+            Some(Overlay::Filter) => &mut self.filter,
+            _ => &mut self.input_buffer,
+        }
+    }
+
+    /// Whether a job for the same video and clip is already in the list and
+    /// hasn't failed or been cancelled. Dry runs write nothing, so they
+    /// don't count.
+    pub fn has_duplicate(&self, url: &str, clip_range: &Option<ClipRange>) -> bool {
+        let url = normalize_url(url);
+        self.jobs.iter().any(|job| {
+            !job.local
+                && !job.simulate
+                && !matches!(job.status(), JobStatus::Failed | JobStatus::Cancelled)
+                && job.clip_range == *clip_range
+                && normalize_url(&job.url) == url
+        })
+    }
+
+    /// Whether a job matches every word of the filter. "status:failed"
+    /// matches the status, other words the title, ignoring case.
+    pub fn matches_filter(&self, job: &Job) -> bool {
+        let title = job.display_title().to_lowercase();
+        self.filter.split_whitespace().all(|word| {
+            let word = word.to_lowercase();
+            match word.strip_prefix("status:") {
+                Some(status) => job.status().label().starts_with(status),
+                None => title.contains(&word),
+            }
+        })
+    }
+
+    /// Indices of the jobs the filter lets through, in the order they're
+    /// listed
+    pub fn visible_jobs(&self) -> Vec<usize> {
+        let mut visible: Vec<usize> = (0..self.jobs.len())
+            .filter(|&i| self.matches_filter(&self.jobs[i]))
+            .collect();
+        // Stable, so jobs that compare equal stay in the order they were added
+        match self.sort_mode {
+            SortMode::Added => {}
+            SortMode::Status => visible.sort_by_key(|&i| status_rank(self.jobs[i].status())),
+            SortMode::Title => {
+                visible.sort_by_cached_key(|&i| self.jobs[i].display_title().to_lowercase())
+            }
+        }
+        visible
+    }
+
+    /// Move the selection to the closest listed job if the filter hides the
+    /// selected one
+    pub fn select_visible(&mut self) {
+        if self.filter.is_empty() {
+            return;
+        }
+        let visible = self.visible_jobs();
+        if visible.contains(&self.selected_index) {
+            return;
+        }
+        if let Some(&next) = visible
+            .iter()
+            .find(|&&i| i > self.selected_index)
+            .or(visible.last())
+        {
+            self.selected_index = next;
+        }
+    }
+
+    /// Select the previous (`up`) or next listed job
+    pub fn move_selection(&mut self, up: bool) {
+        let visible = self.visible_jobs();
+        let next = match visible.iter().position(|&i| i == self.selected_index) {
+            Some(position) if up => position.checked_sub(1),
+            Some(position) => Some(position + 1),
+            None => Some(0),
+        };
+        if let Some(&next) = next.and_then(|position| visible.get(position)) {
+            self.selected_index = next;
         }
     }
 
@@ -114,26 +1218,186 @@ impl AppState {
     }
 
     pub fn active_jobs_count(&self) -> usize {
-        self.jobs.iter().filter(|j| j.status.is_active()).count()
+        self.jobs.iter().filter(|j| j.status().is_active()).count()
     }
 
     pub fn queued_jobs_count(&self) -> usize {
         self.jobs
             .iter()
-            .filter(|j| j.status == JobStatus::Queued)
+            .filter(|j| j.status() == JobStatus::Queued)
             .count()
     }
 
+    /// Download slots no running job takes up
+    pub fn available_slots(&self) -> usize {
+        self.config
+            .max_concurrent_downloads
+            .saturating_sub(self.active_jobs_count())
+    }
+
+    /// Position of each queued job in the order the queue starts them,
+    /// which is the order they were added in whatever the sort, from 1
+    pub fn queue_positions(&self) -> HashMap<Uuid, usize> {
+        self.jobs
+            .iter()
+            .filter(|j| j.status() == JobStatus::Queued && !j.expanding_playlist)
+            .enumerate()
+            .map(|(i, job)| (job.id, i + 1))
+            .collect()
+    }
+
     pub fn completed_jobs_count(&self) -> usize {
-        self.jobs.iter().filter(|j| j.status.is_complete()).count()
+        self.jobs
+            .iter()
+            .filter(|j| j.status().is_complete())
+            .count()
     }
 
     pub fn failed_jobs_count(&self) -> usize {
-        self.jobs.iter().filter(|j| j.status.is_failed()).count()
+        self.jobs.iter().filter(|j| j.status().is_failed()).count()
+    }
+
+    /// Average progress of the active jobs, None if there are none
+    pub fn active_progress(&self) -> Option<f64> {
+        let active: Vec<f64> = self
+            .jobs
+            .iter()
+            .filter(|j| j.status().is_active())
+            .map(|j| j.progress)
+            .collect();
+
+        (!active.is_empty()).then(|| active.iter().sum::<f64>() / active.len() as f64)
+    }
+
+    /// Rough time until all active and queued jobs are done. Queued jobs are
+    /// expected to take as long as the recently completed ones, or the active
+    /// ones before anything has completed. None when there's nothing to go by.
+    pub fn batch_eta(&self) -> Option<Duration> {
+        let active: Vec<&Job> = self
+            .jobs
+            .iter()
+            .filter(|j| j.status().is_active())
+            .collect();
+        let remaining: Vec<Duration> = active.iter().filter_map(|j| j.remaining()).collect();
+        let queued = self.queued_jobs_count() as u32;
+
+        let mut completed: Vec<(Instant, Duration)> = self
+            .jobs
+            .iter()
+            .filter(|j| j.status().is_complete())
+            .filter_map(|j| Some((j.finished_at?, j.elapsed()?)))
+            .collect();
+        completed.sort_by_key(|(finished_at, _)| *finished_at);
+        let recent: Vec<Duration> = completed
+            .iter()
+            .rev()
+            .take(RECENT_COMPLETIONS)
+            .map(|(_, elapsed)| *elapsed)
+            .collect();
+        let per_job = if recent.is_empty() {
+            // Estimated total time of the active jobs instead
+            let totals: Vec<Duration> = active
+                .iter()
+                .filter_map(|j| Some(j.elapsed()? + j.remaining()?))
+                .collect();
+            average(&totals)
+        } else {
+            average(&recent)
+        };
+
+        let queued_total = match per_job {
+            Some(per_job) => per_job * queued,
+            None if queued > 0 => return None,
+            None => Duration::ZERO,
+        };
+        if remaining.is_empty() && queued_total.is_zero() {
+            return None;
+        }
+
+        // Work is spread over the download slots, but can't finish before
+        // the slowest active job
+        let slots = self.config.max_concurrent_downloads.max(1) as u32;
+        let spread = (remaining.iter().sum::<Duration>() + queued_total) / slots;
+        let longest = remaining.iter().max().copied().unwrap_or_default();
+        Some(spread.max(longest))
+    }
+
+    /// Start a batch when jobs start running or waiting, and show its
+    /// summary once none are left. Returns whether the batch just finished
+    /// with at least one job completed.
+    pub fn check_batch_done(&mut self) -> bool {
+        let busy = self.active_jobs_count() + self.queued_jobs_count() > 0;
+        let started_at = match (busy, self.batch_started_at) {
+            (true, None) => {
+                self.batch_started_at = Some(Instant::now());
+                return false;
+            }
+            (false, Some(started_at)) => started_at,
+            _ => return false,
+        };
+        self.batch_started_at = None;
+
+        // Jobs restored from the last session have no finish time
+        let finished = || {
+            self.jobs
+                .iter()
+                .filter(move |j| j.finished_at.is_some_and(|at| at >= started_at))
+        };
+        let completed = finished().filter(|j| j.status().is_complete()).count();
+        let failed = finished().filter(|j| j.status().is_failed()).count();
+        if completed == 0 {
+            return false;
+        }
+
+        // Open overlays aren't taken away from the user
+        if self.overlay.is_none() {
+            self.overlay = Some(Overlay::BatchSummary {
+                completed,
+                failed,
+                elapsed: started_at.elapsed(),
+            });
+        } else {
+            self.set_status(
+                Severity::Info,
+                format!("all done: {} completed, {} failed", completed, failed),
+            );
+        }
+        true
     }
 
+    /// Remove all completed jobs, keeping the selection on the same job or,
+    /// if that one is removed, on the next remaining one
     pub fn clear_completed(&mut self) {
-        self.jobs.retain(|j| !j.status.is_complete());
+        let kept_before_selection = self.jobs[..self.selected_index.min(self.jobs.len())]
+            .iter()
+            .filter(|j| !j.status().is_complete())
+            .count();
+
+        self.jobs.retain(|j| !j.status().is_complete());
+        self.selected_index = kept_before_selection.min(self.jobs.len().saturating_sub(1));
+    }
+
+    /// Swap the selected queued job with the previous (`up`) or next queued
+    /// job, so it's started sooner or later. Other jobs keep their place.
+    pub fn move_queued_job(&mut self, up: bool) {
+        let selected = self.selected_index;
+        let is_queued = |job: &Job| job.status() == JobStatus::Queued;
+        if !self.jobs.get(selected).is_some_and(is_queued) {
+            return;
+        }
+
+        let target = if up {
+            self.jobs[..selected].iter().rposition(is_queued)
+        } else {
+            self.jobs[selected + 1..]
+                .iter()
+                .position(is_queued)
+                .map(|i| selected + 1 + i)
+        };
+        if let Some(target) = target {
+            self.jobs.swap(selected, target);
+            self.selected_index = target;
+        }
     }
 
     pub fn remove_job(&mut self, index: usize) {
@@ -145,6 +1409,112 @@ impl AppState {
         }
     }
 
+    /// Find the next listed failed job after the selection (or before it
+    /// when going backwards), wrapping around the list
+    pub fn find_failed_job(&self, forward: bool) -> Option<usize> {
+        let visible = self.visible_jobs();
+        let len = visible.len();
+        // A selection that's filtered out starts the search at either end
+        let selected = visible.iter().position(|&i| i == self.selected_index);
+        (1..=len)
+            .map(|offset| match (selected, forward) {
+                (Some(position), true) => (position + offset) % len,
+                (Some(position), false) => (position + len - offset) % len,
+                (None, true) => offset - 1,
+                (None, false) => len - offset,
+            })
+            .map(|position| visible[position])
+            .find(|&i| self.jobs[i].status().is_failed())
+    }
+
+    /// Replace a playlist job with one job per entry, in place
+    pub fn expand_playlist_job(&mut self, id: Uuid, entries: Vec<PlaylistEntry>) {
+        let Some(index) = self.jobs.iter().position(|j| j.id == id) else {
+            return;
+        };
+        let job = &mut self.jobs[index];
+        job.expanding_playlist = false;
+
+        // A single video isn't a playlist, keep the job as submitted
+        if entries.len() <= 1 {
+            if let Some(entry) = entries.into_iter().next() {
+                job.title = job.title.take().or(entry.title);
+            }
+            return;
+        }
+
+        let parent = self.jobs.remove(index);
+        let count = entries.len();
+        let children = entries.into_iter().map(|entry| {
+            let mut job = Job::new(entry.url);
+            job.title = entry.title;
+            job.quality = parent.quality.clone();
+            job.clip_range = parent.clip_range.clone();
+            job.audio_only = parent.audio_only;
+            job.simulate = parent.simulate;
+            job
+        });
+        self.jobs.splice(index..index, children);
+
+        // Keep the selection on the same job
+        if self.selected_index > index {
+            self.selected_index += count - 1;
+        }
+    }
+
+    /// Add a job for each chapter split from a download, right after it.
+    /// The chapters are already downloaded, so they're only converted, or
+    /// complete right away at `output_dir` when `convert` is off.
+    pub fn add_chapter_jobs(
+        &mut self,
+        id: Uuid,
+        chapters: Vec<PathBuf>,
+        convert: bool,
+        output_dir: &Path,
+    ) {
+        let Some(index) = self.jobs.iter().position(|j| j.id == id) else {
+            return;
+        };
+        let parent = &self.jobs[index];
+        let count = chapters.len();
+        let children: Vec<Job> = chapters
+            .into_iter()
+            .map(|path| {
+                let mut job = Job::new(parent.url.clone());
+                job.title = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string());
+                job.quality = parent.quality.clone();
+                job.audio_only = parent.audio_only;
+                job.stages.download.status = StageStatus::Complete;
+                if convert {
+                    job.temp_path = Some(path);
+                } else {
+                    // Both are in the output directory, so this is a rename
+                    let output_path = output_dir.join(path.file_name().unwrap_or_default());
+                    let output_path = match std::fs::rename(&path, &output_path) {
+                        Ok(()) => output_path,
+                        Err(_) => path,
+                    };
+                    job.output_path = Some(output_path);
+                    job.stages.conversion.status = StageStatus::Skipped;
+                    job.progress = 100.0;
+                }
+                job
+            })
+            .collect();
+        self.jobs.splice(index + 1..index + 1, children);
+
+        // Keep the selection on the same job
+        if self.selected_index > index {
+            self.selected_index += count;
+        }
+    }
+
+    pub fn selected_job(&self) -> Option<&Job> {
+        self.jobs.get(self.selected_index)
+    }
+
     pub fn get_job_by_id(&self, id: Uuid) -> Option<&Job> {
         self.jobs.iter().find(|j| j.id == id)
     }
@@ -158,23 +1528,96 @@ impl AppState {
 pub enum AppEvent {
     Quit,
     DeleteJob,
+    /// Put a queued or failed job back into the input box to correct it
+    EditJob,
+    CancelJob,
     MoveUp,
     MoveDown,
+    NextFailed,
+    PrevFailed,
+    ToggleLogView,
+    ToggleCompactView,
     InputChar(char),
     InputBackspace,
     InputPaste(String),
+    /// Paste and submit right away
+    PasteSubmit(String),
     ClearInput,
     SubmitUrl,
+    ShowDetails,
+    CloseOverlay,
+    RequeueJob,
+    ToggleDeleteOriginal,
+    ConfirmRequeue,
+    RetryJob,
+    ClearCompleted,
+    OpenOutputFolder,
+    OpenOutputFile,
+    CopyPath,
+    ShowSettings,
+    EditSetting,
+    ToggleHelp,
+    TogglePause,
+    IncreaseConcurrency,
+    DecreaseConcurrency,
+    CycleProfile,
+    CycleSort,
+    ListFormats,
+    ConfirmFormat,
+    MoveJobUp,
+    MoveJobDown,
+    StartFilter,
+    ClearFilter,
+    /// Left click on a screen row
+    ClickRow(u16),
+    /// The terminal changed size
+    Resize,
+    /// Write the job list to a file in the output directory
+    Export,
+    ShowBrowserPicker,
+    ConfirmBrowser,
+}
+
+/// A format yt-dlp can download a video in
+#[derive(Debug, Clone)]
+pub struct FormatEntry {
+    /// Format code for `-f`
+    pub id: String,
+    /// The row of `yt-dlp -F` describing it
+    pub description: String,
+}
+
+/// A single entry of a playlist
+#[derive(Debug, Clone)]
+pub struct PlaylistEntry {
+    pub url: String,
+    pub title: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub enum JobUpdate {
-    Status(JobStatus),
+    Stage(StageKind, StageStatus),
     Progress(f64),
     Speed(String),
     Eta(String),
+    Size(String),
     Title(String),
-    Error(String),
+    Error(StageKind, String),
     TempPath(PathBuf),
     OutputPath(PathBuf),
+    ThumbnailPath(PathBuf),
+    Duration(u64),
+    Height(u32),
+    /// yt-dlp started a step after the download, e.g. "merging"
+    Postprocess(String),
+    /// Bytes a download attempt fetched and how long it took, once it's over
+    Downloaded(u64, Duration),
+    /// The job failed as its output directory wasn't available
+    OutputUnavailable(PathBuf),
+    /// A failed download is being retried, with the number of the retry
+    Retrying(u32),
+    /// Entries found when probing a playlist, empty if probing failed
+    PlaylistEntries(Vec<PlaylistEntry>),
+    /// Files of the chapters split from a download, in order
+    Chapters(Vec<PathBuf>),
 }
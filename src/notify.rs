@@ -0,0 +1,37 @@
+use crate::models::Job;
+use notify_rust::Notification;
+
+/// Show a desktop notification about a job that completed or failed
+pub fn notify_job_finished(job: &Job) {
+    let title = job.display_title();
+    let (summary, body) = if job.status().is_complete() {
+        let output = job
+            .output_path
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_default();
+        (
+            "carbon: download complete",
+            format!("{}\n{}", title, output),
+        )
+    } else {
+        let error = job.error().and_then(|e| e.lines().next()).unwrap_or("");
+        ("carbon: download failed", format!("{}\n{}", title, error))
+    };
+
+    send_notification(summary, &body);
+}
+
+/// Hand a notification to the desktop's notification service. This is best
+/// effort, systems without one simply don't get notified.
+fn send_notification(summary: &str, body: &str) {
+    let mut notification = Notification::new();
+    notification.appname("carbon").summary(summary).body(body);
+
+    // Showing it talks to D-Bus on Linux, which blocks until it's delivered
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = notification.show() {
+            tracing::debug!("notification not shown: {}", e);
+        }
+    });
+}
@@ -1,15 +1,36 @@
-use crate::converter::convert_for_davinci;
-use crate::downloader::download_video;
-use crate::models::{Config, JobStatus, JobUpdate};
-use std::path::PathBuf;
+use crate::config::output_available;
+use crate::converter::{
+    convert_for_davinci, convert_to_dnxhd, convert_to_prores, converted_output_path,
+    kept_original_path, probe_media, ConvertOptions, MediaInfo,
+};
+use crate::downloader::{
+    download_video, parse_rate_limit, partial_path, simulate_download, DownloadOptions,
+};
+use crate::models::{Config, ConversionProfile, Job, JobUpdate, StageKind, StageStatus};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::{mpsc, Semaphore};
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex, OnceCell};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+/// Probes keyed by file path, shared by all jobs so each file is probed at
+/// most once, also when its conversion is retried or run again
+type MediaCache = Arc<Mutex<HashMap<PathBuf, Arc<OnceCell<MediaInfo>>>>>;
+
+/// Tasks of running jobs, each taking up one slot. Tasks remove themselves
+/// when they finish.
+type TaskMap = Arc<std::sync::Mutex<HashMap<Uuid, JoinHandle<()>>>>;
+
 pub struct JobQueue {
-    semaphore: Arc<Semaphore>,
+    /// Number of jobs allowed to run at once, can change at any time
+    max_concurrent: usize,
     update_tx: mpsc::UnboundedSender<(Uuid, JobUpdate)>,
     config: Config,
+    media_cache: MediaCache,
+    tasks: TaskMap,
 }
 
 impl JobQueue {
@@ -19,94 +40,430 @@ impl JobQueue {
         config: Config,
     ) -> Self {
         Self {
-            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
             update_tx,
             config,
+            media_cache: Arc::new(Mutex::new(HashMap::new())),
+            tasks: Arc::new(std::sync::Mutex::new(HashMap::new())),
         }
     }
 
-    pub fn start_job(&self, job_id: Uuid, url: String, quality: String) {
-        let semaphore = self.semaphore.clone();
+    pub fn start_job(&self, job: &Job, quality: String) {
+        if job.simulate {
+            self.start_simulation(job, quality);
+            return;
+        }
+
+        let job_id = job.id;
+        let url = job.url.clone();
+        let audio_only = job.audio_only;
         let update_tx = self.update_tx.clone();
-        let output_dir = PathBuf::from(&self.config.output_directory);
-        let auto_convert = self.config.auto_convert;
+        let base_dir = PathBuf::from(&self.config.output_directory);
+        let output_dir = self.config.output_dir_for(&url, &quality);
+        let mut options = DownloadOptions::new(&self.config, &url, quality, audio_only);
+        options.output_dir = output_dir.clone();
+        options.rate_limit = self.job_rate_limit();
+        options.clip_range = job.clip_range.clone();
+        options.format = job.format.clone();
+        // A job interrupted by quitting or a failure left its partial file
+        options.resume = job
+            .temp_path
+            .as_deref()
+            .is_some_and(|path| partial_path(path).exists());
+        let mut convert_options = ConvertOptions::new(&self.config);
+        convert_options.output_dir = output_dir.clone();
+        // DaVinci conversion is pointless for audio
+        let auto_convert = self.config.auto_convert && !audio_only;
+        let max_retries = self.config.max_retries;
+        let media_cache = self.media_cache.clone();
 
-        tokio::spawn(async move {
-            // Acquire semaphore permit
-            let _permit = semaphore.acquire().await.unwrap();
+        self.spawn_tracked(job_id, async move {
+            // Logged here, as jobs without a free slot are only started later
+            tracing::info!(job = %job_id, url = %url, quality = %options.quality, "starting job");
+            if !output_available(&base_dir, &output_dir) {
+                fail_unavailable_output(job_id, StageKind::Download, output_dir, &update_tx);
+                return;
+            }
 
             // Update status to Downloading
-            let _ = update_tx.send((job_id, JobUpdate::Status(JobStatus::Downloading)));
+            let _ = update_tx.send((
+                job_id,
+                JobUpdate::Stage(StageKind::Download, StageStatus::Running),
+            ));
             let _ = update_tx.send((job_id, JobUpdate::Progress(0.0)));
 
-            // Download video
-            let download_result = download_video(
-                job_id,
-                url.clone(),
-                quality,
-                output_dir.clone(),
-                update_tx.clone(),
-            )
-            .await;
+            // Download video, retrying failures after backing off 2s, 4s, 8s, ...
+            let mut retries = 0;
+            let download_result = loop {
+                let result =
+                    download_video(job_id, url.clone(), options.clone(), update_tx.clone()).await;
+                if result.is_ok() || retries >= max_retries {
+                    break result;
+                }
+                if let Err(e) = &result {
+                    tracing::warn!(job = %job_id, retry = retries + 1, "download failed: {}", e);
+                }
+
+                retries += 1;
+                options.resume = true;
+                let _ = update_tx.send((job_id, JobUpdate::Retrying(retries)));
+                tokio::time::sleep(Duration::from_secs(1 << retries.min(6))).await;
+            };
 
             match download_result {
-                Ok((title, temp_path)) => {
+                Ok((title, temp_path, duration)) => {
                     // Update title if we got it
                     let _ = update_tx.send((job_id, JobUpdate::Title(title)));
+                    // Remember the final file so conversion can be retried on its own
+                    let _ = update_tx.send((job_id, JobUpdate::TempPath(temp_path.clone())));
+                    let _ = update_tx.send((
+                        job_id,
+                        JobUpdate::Stage(StageKind::Download, StageStatus::Complete),
+                    ));
 
                     if auto_convert {
-                        // Update status to Converting
-                        let _ = update_tx.send((job_id, JobUpdate::Status(JobStatus::Converting)));
-                        let _ = update_tx.send((job_id, JobUpdate::Progress(0.0)));
-
-                        // Convert video
-                        let convert_result = convert_for_davinci(
+                        run_conversion(
                             job_id,
-                            temp_path.clone(),
-                            output_dir.clone(),
-                            update_tx.clone(),
+                            temp_path,
+                            convert_options,
+                            duration,
+                            &media_cache,
+                            &update_tx,
                         )
                         .await;
-
-                        match convert_result {
-                            Ok(output_path) => {
-                                // Update status to Complete
-                                let _ =
-                                    update_tx.send((job_id, JobUpdate::OutputPath(output_path)));
-                                let _ = update_tx.send((job_id, JobUpdate::Progress(100.0)));
-                                let _ = update_tx
-                                    .send((job_id, JobUpdate::Status(JobStatus::Complete)));
-                            }
-                            Err(e) => {
-                                // Conversion failed
-                                let _ = update_tx.send((
-                                    job_id,
-                                    JobUpdate::Error(format!("Conversion failed: {}", e)),
-                                ));
-                                let _ =
-                                    update_tx.send((job_id, JobUpdate::Status(JobStatus::Failed)));
-                            }
-                        }
                     } else {
                         // No conversion, just mark as complete
                         let _ = update_tx.send((job_id, JobUpdate::OutputPath(temp_path)));
                         let _ = update_tx.send((job_id, JobUpdate::Progress(100.0)));
-                        let _ = update_tx.send((job_id, JobUpdate::Status(JobStatus::Complete)));
+                        let _ = update_tx.send((
+                            job_id,
+                            JobUpdate::Stage(StageKind::Conversion, StageStatus::Skipped),
+                        ));
                     }
                 }
                 Err(e) => {
                     // Download failed
-                    let _ = update_tx
-                        .send((job_id, JobUpdate::Error(format!("Download failed: {}", e))));
-                    let _ = update_tx.send((job_id, JobUpdate::Status(JobStatus::Failed)));
+                    let _ = update_tx.send((
+                        job_id,
+                        JobUpdate::Error(StageKind::Download, format!("Download failed: {}", e)),
+                    ));
+                    let _ = update_tx.send((
+                        job_id,
+                        JobUpdate::Stage(StageKind::Download, StageStatus::Failed),
+                    ));
+                }
+            }
+        });
+    }
+
+    /// Resolve what a job would download, completing it without an output
+    /// file. Conversion is skipped.
+    fn start_simulation(&self, job: &Job, quality: String) {
+        let job_id = job.id;
+        let url = job.url.clone();
+        let update_tx = self.update_tx.clone();
+        let mut options = DownloadOptions::new(&self.config, &url, quality, job.audio_only);
+        options.clip_range = job.clip_range.clone();
+        options.format = job.format.clone();
+
+        self.spawn_tracked(job_id, async move {
+            tracing::info!(job = %job_id, url = %url, "starting dry run");
+            let _ = update_tx.send((
+                job_id,
+                JobUpdate::Stage(StageKind::Download, StageStatus::Running),
+            ));
+
+            match simulate_download(&url, &options).await {
+                Ok(simulation) => {
+                    if let Some(title) = simulation.title {
+                        let _ = update_tx.send((job_id, JobUpdate::Title(title)));
+                    }
+                    if let Some(size) = simulation.size {
+                        let size = format!("{:.2}MiB", size as f64 / (1024.0 * 1024.0));
+                        let _ = update_tx.send((job_id, JobUpdate::Size(size)));
+                    }
+                    if let Some(duration) = simulation.duration {
+                        let _ = update_tx.send((job_id, JobUpdate::Duration(duration)));
+                    }
+                    if let Some(height) = simulation.height {
+                        let _ = update_tx.send((job_id, JobUpdate::Height(height)));
+                    }
+                    // Skipped first, so the job doesn't look queued for conversion
+                    let _ = update_tx.send((job_id, JobUpdate::Progress(100.0)));
+                    let _ = update_tx.send((
+                        job_id,
+                        JobUpdate::Stage(StageKind::Conversion, StageStatus::Skipped),
+                    ));
+                    let _ = update_tx.send((
+                        job_id,
+                        JobUpdate::Stage(StageKind::Download, StageStatus::Complete),
+                    ));
+                }
+                Err(e) => {
+                    let _ = update_tx.send((
+                        job_id,
+                        JobUpdate::Error(StageKind::Download, format!("Dry run failed: {}", e)),
+                    ));
+                    let _ = update_tx.send((
+                        job_id,
+                        JobUpdate::Stage(StageKind::Download, StageStatus::Failed),
+                    ));
+                }
+            }
+        });
+    }
+
+    /// Convert a file that is already on disk, skipping the download stage.
+    /// Files that weren't downloaded by carbon are kept.
+    pub fn start_conversion(&self, job: &Job, input_path: PathBuf, quality: &str) {
+        let job_id = job.id;
+        let duration = job.duration;
+        let update_tx = self.update_tx.clone();
+        let mut options = ConvertOptions::new(&self.config);
+        options.output_dir = self.config.output_dir_for(&job.url, quality);
+        options.delete_input = !job.local;
+        let base_dir = PathBuf::from(&self.config.output_directory);
+        let media_cache = self.media_cache.clone();
+
+        self.spawn_tracked(job_id, async move {
+            tracing::info!(job = %job_id, input = ?input_path, "starting conversion");
+            if !output_available(&base_dir, &options.output_dir) {
+                let output_dir = options.output_dir.clone();
+                fail_unavailable_output(job_id, StageKind::Conversion, output_dir, &update_tx);
+                return;
+            }
+
+            run_conversion(
+                job_id,
+                input_path,
+                options,
+                duration,
+                &media_cache,
+                &update_tx,
+            )
+            .await;
+        });
+    }
+
+    /// Rate limit of a job started now: the per-download limit, or its share
+    /// of the total one if that's lower. yt-dlp can't change the rate of a
+    /// running download, so the total is split over all slots rather than
+    /// the jobs running at the moment.
+    fn job_rate_limit(&self) -> Option<String> {
+        let per_job = self.config.rate_limit.as_deref().and_then(parse_rate_limit);
+        let share = self
+            .config
+            .total_rate_limit
+            .as_deref()
+            .and_then(parse_rate_limit)
+            .map(|total| (total / self.max_concurrent.max(1) as u64).max(1));
+        let bytes = match (per_job, share) {
+            (Some(per_job), Some(share)) => Some(per_job.min(share)),
+            (per_job, share) => per_job.or(share),
+        };
+        bytes.map(|bytes| bytes.to_string())
+    }
+
+    /// Use new settings for jobs started from now on
+    pub fn set_config(&mut self, config: Config) {
+        self.max_concurrent = config.max_concurrent_downloads;
+        self.config = config;
+    }
+
+    /// Change how many jobs run at once. Lowering it lets running jobs
+    /// finish, new ones only start once fewer than the limit are running.
+    pub fn set_max_concurrent(&mut self, max_concurrent: usize) {
+        self.max_concurrent = max_concurrent;
+    }
+
+    /// Stop a job's task, killing its yt-dlp or ffmpeg process, and remove
+    /// the files it leaves behind
+    pub fn cancel_job(&self, job: &Job) {
+        let Some(handle) = self.tasks.lock().unwrap().remove(&job.id) else {
+            return;
+        };
+        handle.abort();
+        tracing::info!(job = %job.id, "cancelled job");
+
+        let mut leftovers = Vec::new();
+        if let Some(temp_path) = &job.temp_path {
+            // Local files are the user's own, only the conversion is removed
+            if !job.local {
+                leftovers.push(partial_path(temp_path));
+                leftovers.push(temp_path.clone());
+            }
+
+            if job.stages.conversion.status == StageStatus::Running {
+                let options = ConvertOptions::new(&self.config);
+                if let Ok(output_path) = converted_output_path(temp_path, &options) {
+                    leftovers.push(output_path);
+                }
+            }
+        }
+
+        tokio::spawn(async move {
+            // Processes are killed once the aborted task is dropped
+            let _ = handle.await;
+            for path in leftovers {
+                let _ = tokio::fs::remove_file(path).await;
+            }
+        });
+    }
+
+    /// Stop a job's task, killing its yt-dlp or ffmpeg process, but keep
+    /// its files so it can continue where it stopped
+    pub fn stop_job(&self, job_id: Uuid) {
+        if let Some(handle) = self.tasks.lock().unwrap().remove(&job_id) {
+            handle.abort();
+        }
+    }
+
+    /// Stop all running jobs when quitting, waiting until their processes
+    /// are killed. Partial downloads are kept to be resumed next time, while
+    /// half-written conversions are removed as they're redone from the start.
+    pub async fn shutdown(&self, jobs: &[Job]) {
+        let handles: Vec<_> = self.tasks.lock().unwrap().drain().collect();
+        for (job_id, handle) in handles {
+            handle.abort();
+            let _ = handle.await;
+
+            let Some(job) = jobs.iter().find(|job| job.id == job_id) else {
+                continue;
+            };
+            if let (StageStatus::Running, Some(temp_path)) =
+                (job.stages.conversion.status, &job.temp_path)
+            {
+                let options = ConvertOptions::new(&self.config);
+                if let Ok(output_path) = converted_output_path(temp_path, &options) {
+                    let _ = tokio::fs::remove_file(output_path).await;
                 }
             }
+        }
+    }
+
+    /// Spawn the task running a job if a slot is free. Jobs that already have
+    /// a task are skipped, and jobs without a free slot stay queued until
+    /// they are started again once one frees up.
+    fn spawn_tracked(&self, job_id: Uuid, task: impl Future<Output = ()> + Send + 'static) {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.contains_key(&job_id) {
+            return;
+        }
+        if tasks.len() >= self.max_concurrent {
+            return;
+        }
 
-            // Permit is automatically released when _permit goes out of scope
+        // The slot is freed once the task removes itself
+        let registry = self.tasks.clone();
+        let handle = tokio::spawn(async move {
+            task.await;
+            registry.lock().unwrap().remove(&job_id);
         });
+        tasks.insert(job_id, handle);
+    }
+}
+
+/// Fail a job whose output directory isn't available, e.g. on an unplugged
+/// drive, instead of letting yt-dlp or ffmpeg fail with a confusing error.
+/// The app retries it once the directory is back.
+fn fail_unavailable_output(
+    job_id: Uuid,
+    kind: StageKind,
+    output_dir: PathBuf,
+    update_tx: &mpsc::UnboundedSender<(Uuid, JobUpdate)>,
+) {
+    let error = format!("output directory unavailable: {}", output_dir.display());
+    let _ = update_tx.send((job_id, JobUpdate::Error(kind, error)));
+    let _ = update_tx.send((job_id, JobUpdate::OutputUnavailable(output_dir)));
+    let _ = update_tx.send((job_id, JobUpdate::Stage(kind, StageStatus::Failed)));
+}
+
+/// Run the conversion stage of a job and report its outcome
+async fn run_conversion(
+    job_id: Uuid,
+    input_path: PathBuf,
+    options: ConvertOptions,
+    duration: Option<u64>,
+    media_cache: &MediaCache,
+    update_tx: &mpsc::UnboundedSender<(Uuid, JobUpdate)>,
+) {
+    // Update status to Converting
+    let _ = update_tx.send((
+        job_id,
+        JobUpdate::Stage(StageKind::Conversion, StageStatus::Running),
+    ));
+    let _ = update_tx.send((job_id, JobUpdate::Progress(0.0)));
+
+    // Used for all passes, preferring the duration yt-dlp reported
+    let mut media = probe_cached(media_cache, &options.ffprobe, &input_path).await;
+    if let Some(duration) = duration {
+        media.duration = duration;
     }
 
-    pub fn available_slots(&self) -> usize {
-        self.semaphore.available_permits()
+    // Convert video with the configured profile
+    let update_tx_clone = update_tx.clone();
+    let delete_input = options.delete_input.then(|| input_path.clone());
+    let keep_in = options.keep_original.then(|| options.output_dir.clone());
+    let convert_result = match options.profile {
+        ConversionProfile::DaVinciH264 => {
+            convert_for_davinci(job_id, input_path, options, &media, update_tx_clone).await
+        }
+        ConversionProfile::DNxHD => {
+            convert_to_dnxhd(job_id, input_path, options, &media, update_tx_clone).await
+        }
+        ConversionProfile::ProRes => {
+            convert_to_prores(job_id, input_path, options, &media, update_tx_clone).await
+        }
+    };
+
+    match convert_result {
+        Ok(output_path) => {
+            match (delete_input, keep_in) {
+                (Some(input_path), Some(output_dir)) => {
+                    let kept_path = kept_original_path(&input_path, &output_path, &output_dir);
+                    // Both live in the output directory, so this is a rename
+                    if tokio::fs::rename(&input_path, &kept_path).await.is_ok() {
+                        let _ = update_tx.send((job_id, JobUpdate::TempPath(kept_path)));
+                    }
+                }
+                (Some(input_path), None) => {
+                    let _ = tokio::fs::remove_file(input_path).await;
+                }
+                (None, _) => {}
+            }
+
+            // Update status to Complete
+            let _ = update_tx.send((job_id, JobUpdate::OutputPath(output_path)));
+            let _ = update_tx.send((job_id, JobUpdate::Progress(100.0)));
+            let _ = update_tx.send((
+                job_id,
+                JobUpdate::Stage(StageKind::Conversion, StageStatus::Complete),
+            ));
+        }
+        Err(e) => {
+            // Conversion failed
+            let _ = update_tx.send((
+                job_id,
+                JobUpdate::Error(StageKind::Conversion, format!("Conversion failed: {}", e)),
+            ));
+            let _ = update_tx.send((
+                job_id,
+                JobUpdate::Stage(StageKind::Conversion, StageStatus::Failed),
+            ));
+        }
     }
 }
+
+/// Probe a file, reusing an earlier probe of the same path
+async fn probe_cached(cache: &MediaCache, ffprobe: &Path, path: &Path) -> MediaInfo {
+    let cell = cache
+        .lock()
+        .await
+        .entry(path.to_path_buf())
+        .or_default()
+        .clone();
+
+    cell.get_or_init(|| probe_media(ffprobe, path))
+        .await
+        .clone()
+}
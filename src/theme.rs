@@ -0,0 +1,69 @@
+use crate::models::ThemeConfig;
+use ratatui::style::Color;
+
+/// Colors used by the UI, the defaults are a muted palette on pure black
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub bg: Color,
+    pub input_bg: Color,
+    pub accent: Color,
+    pub text: Color,
+    pub dim: Color,
+    pub placeholder: Color,
+    pub yellow: Color,
+    pub green: Color,
+    pub red: Color,
+    pub selection: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            bg: Color::Rgb(0, 0, 0), // Pure black
+            input_bg: Color::Rgb(28, 28, 32),
+            accent: Color::Rgb(100, 140, 200), // Muted blue
+            text: Color::Rgb(200, 200, 200),
+            dim: Color::Rgb(100, 100, 100),
+            placeholder: Color::Rgb(70, 70, 70),
+            yellow: Color::Rgb(220, 180, 100), // Muted yellow
+            green: Color::Rgb(130, 190, 130),  // Muted green
+            red: Color::Rgb(200, 100, 100),    // Muted red
+            selection: Color::Rgb(35, 35, 45),
+        }
+    }
+}
+
+impl Theme {
+    /// The default theme with the colors set in the config replaced
+    pub fn from_config(config: &ThemeConfig) -> Self {
+        let mut theme = Theme::default();
+        for (color, value) in [
+            (&mut theme.bg, &config.bg),
+            (&mut theme.input_bg, &config.input_bg),
+            (&mut theme.accent, &config.accent),
+            (&mut theme.text, &config.text),
+            (&mut theme.dim, &config.dim),
+            (&mut theme.placeholder, &config.placeholder),
+            (&mut theme.yellow, &config.yellow),
+            (&mut theme.green, &config.green),
+            (&mut theme.red, &config.red),
+            (&mut theme.selection, &config.selection),
+        ] {
+            if let Some(parsed) = value.as_deref().and_then(parse_hex) {
+                *color = parsed;
+            }
+        }
+        theme
+    }
+}
+
+/// Parse a hex color like "#64a0c8", the "#" is optional
+pub fn parse_hex(hex: &str) -> Option<Color> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
@@ -0,0 +1,54 @@
+use image::imageops::FilterType;
+use image::{Rgb, RgbImage};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use std::path::Path;
+
+// Thumbnails are shrunk to this size on load, which is already more than
+// any terminal overlay can show
+const MAX_WIDTH: u32 = 160;
+const MAX_HEIGHT: u32 = 90;
+
+/// Decode a thumbnail from disk, downscaled for terminal rendering
+pub fn load_thumbnail(path: &Path) -> Option<RgbImage> {
+    let image = image::open(path).ok()?;
+    Some(image.thumbnail(MAX_WIDTH, MAX_HEIGHT).to_rgb8())
+}
+
+/// Render an image as `▀` half-blocks fitting within `width` x `height` cells.
+/// Each cell covers two pixels: the foreground colors the top one and the
+/// background the bottom one.
+pub fn half_block_lines(image: &RgbImage, width: u16, height: u16) -> Vec<Line<'static>> {
+    let (src_width, src_height) = image.dimensions();
+    if width == 0 || height == 0 || src_width == 0 || src_height == 0 {
+        return Vec::new();
+    }
+
+    // Fit inside the area while preserving aspect ratio
+    let scale = (width as f64 / src_width as f64).min(height as f64 * 2.0 / src_height as f64);
+    let target_width = ((src_width as f64 * scale) as u32).max(1);
+    let target_height = ((src_height as f64 * scale) as u32).max(1);
+    let resized = image::imageops::resize(image, target_width, target_height, FilterType::Triangle);
+
+    (0..target_height)
+        .step_by(2)
+        .map(|y| {
+            let spans: Vec<Span> = (0..target_width)
+                .map(|x| {
+                    let top = resized.get_pixel(x, y);
+                    let bottom = if y + 1 < target_height {
+                        resized.get_pixel(x, y + 1)
+                    } else {
+                        top
+                    };
+                    Span::styled("▀", Style::default().fg(to_color(top)).bg(to_color(bottom)))
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+fn to_color(pixel: &Rgb<u8>) -> Color {
+    Color::Rgb(pixel[0], pixel[1], pixel[2])
+}
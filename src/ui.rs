@@ -1,40 +1,105 @@
-use crate::models::{AppState, JobStatus};
+use crate::deps::COOKIE_BROWSERS;
+use crate::models::{
+    AppState, Config, Job, JobStatus, ListMode, Overlay, Setting, Severity, SortMode, StageStatus,
+    QUALITY_PRESETS,
+};
+use crate::theme::Theme;
+use crate::thumbnail;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Clear, List, ListItem, Padding, Paragraph},
+    widgets::{Block, BorderType, Clear, List, ListItem, Padding, Paragraph, Wrap},
     Frame,
 };
+use std::time::Duration;
+
+/// Frames of the spinner shown while a job has no progress yet
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Every key binding, listed in the help overlay
+const KEY_BINDINGS: [(&str, &str); 29] = [
+    ("enter", "submit url, or show details of the selected job"),
+    ("ctrl+v", "paste url from the clipboard"),
+    ("ctrl+shift+v", "paste and submit, a job per line"),
+    ("esc", "clear input, or close an overlay"),
+    ("↑↓ k j", "select job"),
+    ("K J", "move queued job up / down, also shift+↑↓"),
+    ("n N", "jump to next / previous failed job"),
+    ("/", "filter jobs by title, or e.g. status:failed"),
+    ("S", "sort by added, status or title"),
+    ("l", "toggle log view"),
+    ("v", "toggle compact view"),
+    ("r", "retry failed job"),
+    ("R", "requeue completed job at another quality"),
+    ("F", "list formats, to download one exactly"),
+    ("c", "cancel running job"),
+    ("space", "pause / resume starting queued jobs"),
+    ("+ -", "run more / fewer jobs at once"),
+    ("d", "delete job"),
+    ("e", "edit queued or failed job's url"),
+    ("x", "clear completed jobs"),
+    ("E", "export the job list as json or csv"),
+    ("o", "open output folder"),
+    ("O", "open output file"),
+    ("y", "copy output path"),
+    ("s", "settings"),
+    ("p", "switch profile"),
+    ("b", "pick a browser to use cookies from"),
+    ("?", "toggle this help"),
+    ("q", "quit"),
+];
 
-// Color palette
-const COLOR_BG: Color = Color::Rgb(0, 0, 0); // Pure black
-const COLOR_INPUT_BG: Color = Color::Rgb(28, 28, 32);
-const COLOR_ACCENT: Color = Color::Rgb(100, 140, 200); // Muted blue
-const COLOR_TEXT: Color = Color::Rgb(200, 200, 200);
-const COLOR_DIM: Color = Color::Rgb(100, 100, 100);
-const COLOR_PLACEHOLDER: Color = Color::Rgb(70, 70, 70);
-const COLOR_YELLOW: Color = Color::Rgb(220, 180, 100); // Muted yellow
-const COLOR_GREEN: Color = Color::Rgb(130, 190, 130); // Muted green
-const COLOR_RED: Color = Color::Rgb(200, 100, 100); // Muted red
-const COLOR_SELECTION: Color = Color::Rgb(35, 35, 45);
-
-pub fn render(frame: &mut Frame, state: &AppState) {
+pub fn render(frame: &mut Frame, state: &mut AppState) {
     let area = frame.area();
+    // Filled in again if the job list is rendered
+    state.job_rows.clear();
 
-    // Clear the terminal and fill with pure black
+    // Clear the terminal and fill with the background color
     frame.render_widget(Clear, area);
-    frame.render_widget(Block::default().style(Style::default().bg(COLOR_BG)), area);
+    frame.render_widget(
+        Block::default().style(Style::default().bg(state.theme.bg)),
+        area,
+    );
+
+    if state.missing_dependencies() {
+        render_missing_dependencies(frame, area, state);
+        return;
+    }
 
     if state.has_jobs() {
         render_jobs_view(frame, area, state);
     } else {
         render_welcome_view(frame, area, state);
     }
+
+    match state.overlay {
+        Some(Overlay::Details { .. }) => render_details_overlay(frame, area, state),
+        Some(Overlay::QualityPicker {
+            index,
+            delete_original,
+        }) => render_quality_picker(frame, area, index, delete_original, &state.theme),
+        Some(Overlay::Help) => render_help_overlay(frame, area, &state.theme),
+        Some(Overlay::ConfirmQuit) => render_confirm_quit(frame, area, state),
+        Some(Overlay::Settings { index, editing }) => {
+            render_settings_overlay(frame, area, state, index, editing)
+        }
+        Some(Overlay::Formats { index }) => render_formats_overlay(frame, area, state, index),
+        Some(Overlay::BrowserPicker { index }) => render_browser_picker(frame, area, state, index),
+        Some(Overlay::BatchSummary {
+            completed,
+            failed,
+            elapsed,
+        }) => render_batch_summary(frame, area, completed, failed, elapsed, &state.theme),
+        // The filter is typed into the summary line
+        Some(Overlay::Filter) | None => {}
+    }
 }
 
 /// Render the welcome view - shown when there are no jobs
 fn render_welcome_view(frame: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
+
     // Center everything vertically
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -53,7 +118,7 @@ fn render_welcome_view(frame: &mut Frame, area: Rect, state: &AppState) {
     let title = Paragraph::new(Line::from(vec![Span::styled(
         "carbon",
         Style::default()
-            .fg(COLOR_ACCENT)
+            .fg(theme.accent)
             .add_modifier(Modifier::BOLD),
     )]))
     .alignment(Alignment::Center);
@@ -62,20 +127,109 @@ fn render_welcome_view(frame: &mut Frame, area: Rect, state: &AppState) {
     // Input box - centered horizontally with max width
     let input_area = center_horizontally(chunks[3], 60);
     render_input_box(frame, input_area, state, "paste a url...");
+    render_status_message(frame, center_horizontally(chunks[4], 60), state);
 
     // Shortcuts
-    let shortcuts =
-        create_shortcuts_line(&[("enter", "submit"), ("ctrl+v", "paste"), ("q", "quit")]);
+    let shortcuts = create_shortcuts_line(
+        &[
+            ("enter", "submit"),
+            ("ctrl+v", "paste"),
+            ("s", "settings"),
+            ("?", "help"),
+            ("q", "quit"),
+        ],
+        theme,
+    );
     let shortcuts_widget = Paragraph::new(shortcuts).alignment(Alignment::Center);
     frame.render_widget(shortcuts_widget, chunks[5]);
+
+    // Tool versions, so users can check they're up to date
+    let mut versions = vec![Span::styled(
+        format!(
+            "yt-dlp {} · ffmpeg {}",
+            state.dependencies.ytdlp.as_deref().unwrap_or("?"),
+            state.dependencies.ffmpeg.as_deref().unwrap_or("?"),
+        ),
+        Style::default().fg(theme.placeholder),
+    )];
+    if let Some(latest) = state.dependencies.ytdlp_update() {
+        versions.push(Span::styled(
+            format!(" · yt-dlp {} available", latest),
+            Style::default().fg(theme.yellow),
+        ));
+    }
+    let versions = Paragraph::new(Line::from(versions)).alignment(Alignment::Center);
+    let versions_area = Rect::new(area.x, area.bottom().saturating_sub(2), area.width, 1);
+    frame.render_widget(versions, versions_area);
+}
+
+/// Render the status message in the first line of `area`
+fn render_status_message(frame: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
+    if let Some((message, severity, _)) = &state.status_message {
+        let color = match severity {
+            Severity::Info => theme.dim,
+            Severity::Warn => theme.yellow,
+            Severity::Error => theme.red,
+        };
+        let line = Paragraph::new(Span::styled(message.clone(), Style::default().fg(color)))
+            .alignment(Alignment::Center);
+        frame.render_widget(line, Rect::new(area.x, area.y, area.width, 1));
+    }
+}
+
+/// Render the error screen shown instead of everything else when yt-dlp or
+/// ffmpeg can't be found
+fn render_missing_dependencies(frame: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let missing = state.dependencies.missing();
+    let overlay_area = centered_rect(area, 64, missing.len() as u16 * 2 + 8);
+
+    let block = overlay_block(" missing dependencies ", theme);
+    let inner = block.inner(overlay_area);
+    frame.render_widget(block, overlay_area);
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "carbon needs these tools on your PATH:",
+            Style::default().fg(theme.text),
+        )),
+        Line::from(""),
+    ];
+    for tool in &missing {
+        let hint = match *tool {
+            "yt-dlp" => "pip install yt-dlp, or your package manager",
+            _ => "install ffmpeg with your package manager",
+        };
+        lines.push(Line::from(vec![
+            Span::styled("✗ ", Style::default().fg(theme.red)),
+            Span::styled(
+                tool.to_string(),
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+            ),
+        ]));
+        lines.push(Line::from(Span::styled(
+            format!("  {}", hint),
+            Style::default().fg(theme.dim),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "install them, or set ytdlp_path or ffmpeg_path, and restart",
+        Style::default().fg(theme.dim),
+    )));
+    lines.push(create_shortcuts_line(&[("q", "quit")], theme));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
 }
 
 /// Render the jobs view - shown when there are active jobs
-fn render_jobs_view(frame: &mut Frame, area: Rect, state: &AppState) {
+fn render_jobs_view(frame: &mut Frame, area: Rect, state: &mut AppState) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(5),    // Jobs list
+            Constraint::Length(1), // Summary
             Constraint::Length(3), // Input box
             Constraint::Length(2), // Shortcuts
         ])
@@ -83,29 +237,148 @@ fn render_jobs_view(frame: &mut Frame, area: Rect, state: &AppState) {
 
     // Jobs list
     render_jobs_list(frame, chunks[0], state);
+    let theme = &state.theme;
+
+    render_summary(frame, chunks[1].inner(Margin::new(2, 0)), state);
 
     // Input box - with horizontal padding
-    let input_area = chunks[1].inner(Margin::new(2, 0));
+    let input_area = chunks[2].inner(Margin::new(2, 0));
     render_input_box(frame, input_area, state, "paste another url...");
 
     // Shortcuts
     let shortcuts = if state.input_buffer.is_empty() {
-        create_shortcuts_line(&[
+        let mut shortcuts = vec![
             ("enter", "submit"),
             ("ctrl+v", "paste"),
             ("d", "delete"),
+            ("r", "retry"),
+            ("c", "cancel"),
+        ];
+        if state.completed_jobs_count() > 0 {
+            shortcuts.push(("x", "clear done"));
+        }
+        let concurrency = format!("{} at once", state.config.max_concurrent_downloads);
+        shortcuts.extend([
             ("↑↓", "navigate"),
+            ("+-", concurrency.as_str()),
+            ("?", "help"),
             ("q", "quit"),
-        ])
+        ]);
+        create_shortcuts_line(&shortcuts, theme)
     } else {
-        create_shortcuts_line(&[("enter", "submit"), ("ctrl+v", "paste"), ("esc", "clear")])
+        create_shortcuts_line(
+            &[("enter", "submit"), ("ctrl+v", "paste"), ("esc", "clear")],
+            theme,
+        )
     };
-    let shortcuts_widget = Paragraph::new(shortcuts).alignment(Alignment::Center);
-    frame.render_widget(shortcuts_widget, chunks[2]);
+    // Status messages take the place of the shortcuts while they're shown
+    if state.status_message.is_some() {
+        render_status_message(frame, chunks[3].inner(Margin::new(2, 0)), state);
+    } else {
+        let shortcuts_widget = Paragraph::new(shortcuts).alignment(Alignment::Center);
+        frame.render_widget(shortcuts_widget, chunks[3]);
+    }
+
+    // Session stats below, once something has been downloaded
+    if let Some(stats) = create_stats_line(state) {
+        let stats_area = Rect {
+            y: chunks[3].y + 1,
+            height: 1,
+            ..chunks[3]
+        };
+        frame.render_widget(
+            Paragraph::new(stats).alignment(Alignment::Center),
+            stats_area,
+        );
+    }
+}
+
+/// What this session downloaded, and how many downloads there were ever, e.g.
+/// "this session: 3 downloaded · 1.21GiB · 8.4MiB/s avg · 412 all time"
+fn create_stats_line(state: &AppState) -> Option<Line<'static>> {
+    let stats = &state.session_stats;
+    if stats.completed == 0 && stats.bytes == 0 {
+        return None;
+    }
+
+    let mut parts = vec![
+        format!("{} downloaded", stats.completed),
+        format_bytes(stats.bytes),
+    ];
+    if let Some(speed) = stats.average_speed() {
+        parts.push(format!("{}/s avg", format_bytes(speed)));
+    }
+    parts.push(format!("{} all time", state.lifetime_stats.completed));
+
+    Some(Line::from(Span::styled(
+        format!("this session: {}", parts.join(" · ")),
+        Style::default().fg(state.theme.dim),
+    )))
+}
+
+/// Render the filter, job counts per status and the overall progress of
+/// active jobs and the time left for the batch, e.g.
+/// "3 active · 5 queued · 12 complete · 1 failed  42%  ~12:30 left"
+fn render_summary(frame: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let counts = [
+        (state.active_jobs_count(), "active", theme.accent),
+        (state.queued_jobs_count(), "queued", theme.dim),
+        (state.completed_jobs_count(), "complete", theme.green),
+        (state.failed_jobs_count(), "failed", theme.red),
+    ];
+
+    let mut spans: Vec<Span> = Vec::new();
+    let typing = state.overlay == Some(Overlay::Filter);
+    if typing || !state.filter.is_empty() {
+        spans.push(Span::styled(
+            format!("/{}{}", state.filter, if typing { "_" } else { "" }),
+            Style::default().fg(theme.accent),
+        ));
+        spans.push(Span::styled(
+            format!(" ({} shown)  ", state.visible_jobs().len()),
+            Style::default().fg(theme.dim),
+        ));
+    }
+
+    if state.sort_mode != SortMode::Added {
+        spans.push(Span::styled(
+            format!("⇅ {}  ", state.sort_mode.label()),
+            Style::default().fg(theme.accent),
+        ));
+    }
+
+    let counts_start = spans.len();
+    for (count, label, color) in counts.into_iter().filter(|(count, ..)| *count > 0) {
+        if spans.len() > counts_start {
+            spans.push(Span::styled(" · ", Style::default().fg(theme.dim)));
+        }
+        spans.push(Span::styled(
+            format!("{} {}", count, label),
+            Style::default().fg(color),
+        ));
+    }
+
+    if let Some(progress) = state.active_progress() {
+        spans.push(Span::styled(
+            format!("  {:.0}%", progress),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if let Some(eta) = state.batch_eta() {
+        spans.push(Span::styled(
+            format!("  ~{} left", format_elapsed(eta)),
+            Style::default().fg(theme.dim),
+        ));
+    }
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
 /// Render the input box with dark grey background
 fn render_input_box(frame: &mut Frame, area: Rect, state: &AppState, placeholder: &str) {
+    let theme = &state.theme;
     let input_text = if state.input_buffer.is_empty() {
         placeholder.to_string()
     } else {
@@ -113,55 +386,143 @@ fn render_input_box(frame: &mut Frame, area: Rect, state: &AppState, placeholder
     };
 
     let text_color = if state.input_buffer.is_empty() {
-        COLOR_PLACEHOLDER
+        theme.placeholder
     } else {
-        COLOR_ACCENT
+        theme.accent
     };
 
     let input = Paragraph::new(format!(" {}", input_text))
-        .style(Style::default().fg(text_color).bg(COLOR_INPUT_BG))
+        .style(Style::default().fg(text_color).bg(theme.input_bg))
         .block(
             Block::default()
-                .style(Style::default().bg(COLOR_INPUT_BG))
+                .style(Style::default().bg(theme.input_bg))
                 .padding(Padding::vertical(1)),
         );
 
     frame.render_widget(input, area);
+
+    // Show the active profile, and remind the user that downloads are throttled
+    let mut indicators = Vec::new();
+    if let Some(profile) = &state.config.active_profile {
+        indicators.push(Span::styled(
+            format!("◆ {} ", profile),
+            Style::default().fg(theme.accent).bg(theme.input_bg),
+        ));
+    }
+    if let Some(rate_limit) = &state.config.rate_limit {
+        indicators.push(Span::styled(
+            format!("⇣ {}/s ", rate_limit),
+            Style::default().fg(theme.yellow).bg(theme.input_bg),
+        ));
+    }
+    if let Some(total_rate_limit) = &state.config.total_rate_limit {
+        indicators.push(Span::styled(
+            format!("⇣ {}/s total ", total_rate_limit),
+            Style::default().fg(theme.yellow).bg(theme.input_bg),
+        ));
+    }
+    if !indicators.is_empty() {
+        let indicator = Paragraph::new(Line::from(indicators))
+            .alignment(Alignment::Right)
+            .block(Block::default().padding(Padding::vertical(1)));
+        frame.render_widget(indicator, area);
+    }
 }
 
 /// Render the jobs list with inline progress bars
-fn render_jobs_list(frame: &mut Frame, area: Rect, state: &AppState) {
+fn render_jobs_list(frame: &mut Frame, area: Rect, state: &mut AppState) {
+    let theme = &state.theme;
     let list_area = area.inner(Margin::new(2, 1));
 
-    let items: Vec<ListItem> = state
-        .jobs
+    // Scroll just enough to keep the selected job in view, counting only the
+    // jobs the filter lets through
+    let listed = state.visible_jobs();
+    let heights: Vec<u16> = listed
         .iter()
-        .enumerate()
+        .map(|&i| job_height(&state.jobs[i], state.list_mode))
+        .collect();
+    let selected = listed
+        .iter()
+        .position(|&i| i == state.selected_index)
+        .unwrap_or(0);
+    state.list_offset = scroll_offset(&heights, selected, state.list_offset, list_area.height);
+    let visible = visible_count(&heights[state.list_offset..], list_area.height);
+
+    // Remember where each job ends up, for mouse clicks
+    let mut y = list_area.y;
+    for (&index, &height) in listed
+        .iter()
+        .zip(&heights)
+        .skip(state.list_offset)
+        .take(visible)
+    {
+        state.job_rows.push((y, height, index));
+        y += height;
+    }
+    if visible < heights.len() {
+        let first = state.list_offset + 1;
+        let last = state.list_offset + visible;
+        let indicator = Paragraph::new(Span::styled(
+            format!("{}–{} of {} ", first, last, heights.len()),
+            Style::default().fg(theme.dim),
+        ))
+        .alignment(Alignment::Right);
+        let indicator_area = Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1);
+        frame.render_widget(indicator, indicator_area);
+    }
+
+    if state.finishing {
+        let banner = Paragraph::new(Span::styled(
+            "⏳ QUITTING when done, q to stop now ",
+            Style::default()
+                .fg(theme.yellow)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .alignment(Alignment::Right);
+        frame.render_widget(banner, Rect::new(area.x, area.y, area.width, 1));
+    } else if state.paused {
+        let banner = Paragraph::new(Span::styled(
+            "⏸ PAUSED ",
+            Style::default()
+                .fg(theme.yellow)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .alignment(Alignment::Right);
+        frame.render_widget(banner, Rect::new(area.x, area.y, area.width, 1));
+    }
+
+    if listed.is_empty() {
+        let empty = Paragraph::new(Span::styled(
+            "no jobs match the filter",
+            Style::default().fg(theme.dim),
+        ));
+        frame.render_widget(empty, list_area);
+        return;
+    }
+
+    if state.list_mode == ListMode::Log {
+        render_log_list(frame, list_area, state, &listed);
+        return;
+    }
+
+    let positions = state.queue_positions();
+    // At least the first queued job is next, even while every slot is taken
+    let next_count = state.available_slots().max(1);
+    let items: Vec<ListItem> = listed
+        .iter()
+        .skip(state.list_offset)
+        .map(|&idx| (idx, &state.jobs[idx]))
         .flat_map(|(idx, job)| {
             let is_selected = idx == state.selected_index;
 
-            let (status_symbol, status_color) = match job.status {
-                JobStatus::Queued => ("○", COLOR_DIM),
-                JobStatus::Downloading => ("●", COLOR_ACCENT),
-                JobStatus::Converting => ("◐", COLOR_YELLOW),
-                JobStatus::Complete => ("✓", COLOR_GREEN),
-                JobStatus::Failed => ("✗", COLOR_RED),
-            };
-
-            let title = job.display_title();
-            let title_display = if title.len() > 50 {
-                format!("{}...", &title[..47])
+            let (status_symbol, status_color, status_text) = status_display(&job.status(), theme);
+            let status_text = if job.retries > 0 && job.status() == JobStatus::Downloading {
+                format!("retrying {}/{}", job.retries, state.config.max_retries)
             } else {
-                title
+                status_text.to_string()
             };
 
-            let status_text = match job.status {
-                JobStatus::Queued => "queued",
-                JobStatus::Downloading => "downloading",
-                JobStatus::Converting => "converting",
-                JobStatus::Complete => "complete",
-                JobStatus::Failed => "failed",
-            };
+            let title_display = truncate_end(&job.display_title(), 50);
 
             // Build the main job line
             let mut main_line = vec![
@@ -175,48 +536,100 @@ fn render_jobs_list(frame: &mut Frame, area: Rect, state: &AppState) {
                         .fg(status_color)
                         .add_modifier(Modifier::DIM),
                 ),
-                Span::styled(title_display, Style::default().fg(COLOR_TEXT)),
             ];
+            if job.audio_only {
+                main_line.push(Span::styled("♪ ", Style::default().fg(theme.accent)));
+            }
+            main_line.push(Span::styled(title_display, Style::default().fg(theme.text)));
+            if let Some(format) = &job.format {
+                main_line.push(Span::styled(
+                    format!(" [fmt:{}]", format),
+                    Style::default().fg(theme.dim),
+                ));
+            } else if let Some(quality) = &job.quality {
+                main_line.push(Span::styled(
+                    format!(" [{}]", quality),
+                    Style::default().fg(theme.dim),
+                ));
+            }
+            if let Some((start, end)) = &job.clip_range {
+                main_line.push(Span::styled(
+                    format!(" [{}-{}]", start, end),
+                    Style::default().fg(theme.dim),
+                ));
+            }
+            if job.simulate {
+                main_line.push(Span::styled(
+                    " [dry run]",
+                    Style::default().fg(theme.yellow),
+                ));
+            }
 
             // Add extra info for certain states
-            if job.status.is_complete() {
+            if job.status().is_complete() {
+                if let Some(elapsed) = job.elapsed() {
+                    main_line.push(Span::styled(
+                        format!("  {}", format_elapsed(elapsed)),
+                        Style::default().fg(theme.dim),
+                    ));
+                }
+                // Dry runs have no file, only the size it would have
+                if let Some(size) = job.size.as_ref().filter(|_| job.simulate) {
+                    main_line.push(Span::styled(
+                        format!("  ~{}", size),
+                        Style::default().fg(theme.dim),
+                    ));
+                }
                 if let Some(path) = &job.output_path {
-                    let path_str = path.to_string_lossy();
-                    let display_path = if path_str.len() > 25 {
-                        format!("  ...{}", &path_str[path_str.len() - 22..])
-                    } else {
-                        format!("  {}", path_str)
-                    };
                     main_line.push(Span::styled(
-                        display_path,
-                        Style::default().fg(COLOR_DIM).add_modifier(Modifier::DIM),
+                        format!("  {}", truncate_start(&path.to_string_lossy(), 25)),
+                        Style::default().fg(theme.dim).add_modifier(Modifier::DIM),
                     ));
                 }
-            } else if job.status.is_failed() {
-                if let Some(error) = &job.error {
-                    let error_display = if error.len() > 40 {
-                        format!("  {}...", &error[..37])
-                    } else {
-                        format!("  {}", error)
-                    };
-                    main_line.push(Span::styled(error_display, Style::default().fg(COLOR_RED)));
+            } else if job.status().is_failed() {
+                if let Some(error) = job.error() {
+                    main_line.push(Span::styled(
+                        format!("  {}", truncate_end(error, 40)),
+                        Style::default().fg(theme.red),
+                    ));
                 }
+            } else if let Some(&position) = positions.get(&job.id) {
+                let position = if position <= next_count {
+                    "next".to_string()
+                } else {
+                    format!("#{} in queue", position)
+                };
+                main_line.push(Span::styled(
+                    format!("  {}", position),
+                    Style::default().fg(theme.dim),
+                ));
+            }
+
+            // The compact view has no room for more than a short bar
+            let detailed = state.list_mode == ListMode::Detailed;
+            if job.status().is_active() && !detailed {
+                main_line.extend(create_mini_progress(job, &state.config, theme, state.tick));
             }
 
             let main_style = if is_selected {
-                Style::default().bg(COLOR_SELECTION)
+                Style::default().bg(theme.selection)
             } else {
                 Style::default()
             };
+            let main_style = if state.is_pending_delete(job.id) {
+                main_style.add_modifier(Modifier::CROSSED_OUT)
+            } else {
+                main_style
+            };
 
             let mut items = vec![ListItem::new(Line::from(main_line)).style(main_style)];
 
             // Add progress bar for active jobs
-            if job.status.is_active() {
+            if job.status().is_active() && detailed {
                 let progress_line =
-                    create_progress_line(job.progress, &job.speed, &job.eta, &job.status);
+                    create_progress_line(job, &state.config, theme, state.tick, list_area.width);
                 let progress_style = if is_selected {
-                    Style::default().bg(COLOR_SELECTION)
+                    Style::default().bg(theme.selection)
                 } else {
                     Style::default()
                 };
@@ -233,74 +646,886 @@ fn render_jobs_list(frame: &mut Frame, area: Rect, state: &AppState) {
     frame.render_widget(list, list_area);
 }
 
+/// Render the jobs list as a dense log, exactly one line per job
+fn render_log_list(frame: &mut Frame, area: Rect, state: &AppState, listed: &[usize]) {
+    let theme = &state.theme;
+    let items: Vec<ListItem> = listed
+        .iter()
+        .skip(state.list_offset)
+        .map(|&idx| {
+            let job = &state.jobs[idx];
+            let (status_symbol, status_color, _) = status_display(&job.status(), theme);
+            // The start of the id is enough to tell jobs apart
+            let handle = job.id.simple().to_string()[..6].to_string();
+
+            let mut spans = vec![
+                Span::styled(format!(" {} ", handle), Style::default().fg(theme.dim)),
+                Span::styled(
+                    format!("{} ", status_symbol),
+                    Style::default().fg(status_color),
+                ),
+                Span::styled(
+                    format!("{:>5.1}% ", job.progress),
+                    Style::default().fg(theme.text).add_modifier(Modifier::DIM),
+                ),
+            ];
+            // The title gets the rest of the row
+            let used: usize = spans.iter().map(|span| span.width()).sum();
+            let title_width = (area.width as usize).saturating_sub(used);
+            spans.push(Span::styled(
+                truncate_end(&job.display_title(), title_width),
+                Style::default().fg(theme.text),
+            ));
+            let line = Line::from(spans);
+
+            let style = if idx == state.selected_index {
+                Style::default().bg(theme.selection)
+            } else {
+                Style::default()
+            };
+            let style = if state.is_pending_delete(job.id) {
+                style.add_modifier(Modifier::CROSSED_OUT)
+            } else {
+                style
+            };
+            ListItem::new(line).style(style)
+        })
+        .collect();
+
+    frame.render_widget(List::new(items), area);
+}
+
+/// Number of lines a job takes up in the list
+fn job_height(job: &Job, list_mode: ListMode) -> u16 {
+    match list_mode {
+        // Active jobs get a progress bar and a blank line below it
+        ListMode::Detailed if job.status().is_active() => 3,
+        _ => 1,
+    }
+}
+
+/// First job to show so that the selected one is visible, moving as little
+/// as possible from the previous offset
+fn scroll_offset(heights: &[u16], selected: usize, offset: usize, height: u16) -> usize {
+    let selected = selected.min(heights.len().saturating_sub(1));
+    let mut offset = offset.min(selected);
+    while offset < selected && heights[offset..=selected].iter().sum::<u16>() > height {
+        offset += 1;
+    }
+    offset
+}
+
+/// Number of jobs, starting at the first of `heights`, that fit in `height`
+fn visible_count(heights: &[u16], height: u16) -> usize {
+    let mut used = 0;
+    heights
+        .iter()
+        .take_while(|&&h| {
+            used += h;
+            used <= height
+        })
+        .count()
+}
+
+/// Symbol, color and label for a job status
+fn status_display(status: &JobStatus, theme: &Theme) -> (&'static str, Color, &'static str) {
+    let (symbol, color) = match status {
+        JobStatus::Queued => ("○", theme.dim),
+        JobStatus::Downloading => ("●", theme.accent),
+        JobStatus::Processing => ("◑", theme.accent),
+        JobStatus::Converting => ("◐", theme.yellow),
+        JobStatus::Complete => ("✓", theme.green),
+        JobStatus::Failed => ("✗", theme.red),
+        JobStatus::Cancelled => ("⊘", theme.dim),
+    };
+    (symbol, color, status.label())
+}
+
+/// Label and color for the status of a single job stage
+fn stage_display(status: StageStatus, theme: &Theme) -> (&'static str, Color) {
+    match status {
+        StageStatus::Pending => ("pending", theme.dim),
+        StageStatus::Running => ("running", theme.accent),
+        StageStatus::Complete => ("complete", theme.green),
+        StageStatus::Failed => ("failed", theme.red),
+        StageStatus::Cancelled => ("cancelled", theme.dim),
+        StageStatus::Skipped => ("skipped", theme.dim),
+    }
+}
+
+/// Render the detail overlay for the selected job
+fn render_details_overlay(frame: &mut Frame, area: Rect, state: &mut AppState) {
+    let theme = &state.theme;
+    let Some(job) = state.selected_job() else {
+        return;
+    };
+
+    let overlay_area = centered_rect(area, 80, 30);
+    frame.render_widget(Clear, overlay_area);
+
+    let block = overlay_block(" details ", theme);
+    let inner = block.inner(overlay_area);
+    frame.render_widget(block, overlay_area);
+
+    let field = |label: &str, value: String, color: Color| {
+        Line::from(vec![
+            Span::styled(format!("{:<10}", label), Style::default().fg(theme.dim)),
+            Span::styled(value, Style::default().fg(color)),
+        ])
+    };
+
+    let mut lines = vec![
+        field("title", job.display_title(), theme.text),
+        field("url", job.url.clone(), theme.text),
+    ];
+    if let Some(height) = job.height {
+        let requested = job.quality.as_deref().unwrap_or(&state.selected_quality);
+        let quality = format!("{}p", height);
+        // Point out when the source didn't have the requested quality
+        let value = if job.format.is_some() || requested == "best" || requested == quality {
+            quality
+        } else {
+            format!("{} (requested {})", quality, requested)
+        };
+        lines.push(field("quality", value, theme.text));
+    }
+    if let Some(format) = &job.format {
+        lines.push(field("format", format.clone(), theme.text));
+    }
+    if let Some((start, end)) = &job.clip_range {
+        lines.push(field("clip", format!("{} - {}", start, end), theme.text));
+    }
+    if let Some(path) = &job.output_path {
+        lines.push(field(
+            "output",
+            path.to_string_lossy().to_string(),
+            theme.text,
+        ));
+    }
+    for (label, stage) in [
+        ("download", &job.stages.download),
+        ("convert", &job.stages.conversion),
+    ] {
+        let (text, color) = stage_display(stage.status, theme);
+        lines.push(field(label, text.to_string(), color));
+        // The whole error, yt-dlp and ffmpeg output spans many lines
+        if let Some(error) = &stage.error {
+            for error_line in error.lines() {
+                lines.push(field("", error_line.to_string(), theme.red));
+            }
+        }
+    }
+
+    // Wrapped height, close enough to limit scrolling
+    let width = inner.width.max(1) as usize;
+    let height: usize = lines
+        .iter()
+        .map(|line| line.width().max(1).div_ceil(width))
+        .sum();
+    let max_scroll = height.saturating_sub(inner.height as usize) as u16;
+    let scroll = match &mut state.overlay {
+        Some(Overlay::Details { scroll }) => {
+            *scroll = (*scroll).min(max_scroll);
+            *scroll
+        }
+        _ => 0,
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(height as u16 + 1),
+            Constraint::Min(0), // Thumbnail
+        ])
+        .split(inner);
+
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0)),
+        chunks[0],
+    );
+    if max_scroll > 0 {
+        let hint = Paragraph::new(Span::styled(
+            format!(" ↑/↓ scroll {}/{} ", scroll, max_scroll),
+            Style::default().fg(theme.dim),
+        ))
+        .alignment(Alignment::Right);
+        frame.render_widget(
+            hint,
+            Rect::new(
+                overlay_area.x,
+                overlay_area.bottom().saturating_sub(1),
+                overlay_area.width,
+                1,
+            ),
+        );
+    }
+
+    if let Some(preview) = &state.preview {
+        let thumbnail_area = chunks[1];
+        let thumbnail_lines =
+            thumbnail::half_block_lines(preview, thumbnail_area.width, thumbnail_area.height);
+        frame.render_widget(
+            Paragraph::new(thumbnail_lines).alignment(Alignment::Center),
+            thumbnail_area,
+        );
+    }
+}
+
+/// Render the formats of the selected job as yt-dlp lists them
+fn render_formats_overlay(frame: &mut Frame, area: Rect, state: &AppState, index: usize) {
+    let theme = &state.theme;
+    let count = state.formats.as_ref().map_or(1, Vec::len) as u16;
+    let overlay_area = centered_rect(area, 100, count + 5);
+    frame.render_widget(Clear, overlay_area);
+
+    let block = overlay_block(" formats ", theme);
+    let inner = block.inner(overlay_area);
+    frame.render_widget(block, overlay_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(2)])
+        .split(inner);
+
+    let lines: Vec<Line> = match &state.formats {
+        None => vec![Line::from(Span::styled(
+            "listing formats...",
+            Style::default().fg(theme.dim),
+        ))],
+        Some(formats) if formats.is_empty() => vec![Line::from(Span::styled(
+            "no formats found",
+            Style::default().fg(theme.dim),
+        ))],
+        Some(formats) => {
+            // Scroll just enough to keep the selected format in view
+            let visible = chunks[0].height.max(1) as usize;
+            let offset = index.saturating_sub(visible - 1);
+            formats
+                .iter()
+                .enumerate()
+                .skip(offset)
+                .take(visible)
+                .map(|(i, format)| {
+                    let style = if i == index {
+                        Style::default().fg(theme.accent).bg(theme.selection)
+                    } else {
+                        Style::default().fg(theme.text)
+                    };
+                    Line::from(Span::styled(format.description.clone(), style))
+                })
+                .collect()
+        }
+    };
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let shortcuts = create_shortcuts_line(&[("enter", "download"), ("esc", "cancel")], theme);
+    frame.render_widget(Paragraph::new(vec![Line::from(""), shortcuts]), chunks[1]);
+}
+
+/// Render the quality picker used to requeue a completed job
+fn render_quality_picker(
+    frame: &mut Frame,
+    area: Rect,
+    index: usize,
+    delete_original: bool,
+    theme: &Theme,
+) {
+    let overlay_area = centered_rect(area, 36, QUALITY_PRESETS.len() as u16 + 6);
+    frame.render_widget(Clear, overlay_area);
+
+    let block = overlay_block(" requeue as ", theme);
+    let inner = block.inner(overlay_area);
+    frame.render_widget(block, overlay_area);
+
+    let mut lines: Vec<Line> = QUALITY_PRESETS
+        .iter()
+        .enumerate()
+        .map(|(i, quality)| {
+            if i == index {
+                Line::from(Span::styled(
+                    format!("› {}", quality),
+                    Style::default()
+                        .fg(theme.accent)
+                        .bg(theme.selection)
+                        .add_modifier(Modifier::BOLD),
+                ))
+            } else {
+                Line::from(Span::styled(
+                    format!("  {}", quality),
+                    Style::default().fg(theme.text),
+                ))
+            }
+        })
+        .collect();
+
+    let checkbox = if delete_original { "[x]" } else { "[ ]" };
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!("{} delete original", checkbox),
+        Style::default().fg(theme.dim),
+    )));
+    lines.push(create_shortcuts_line(
+        &[("enter", "requeue"), ("d", "toggle"), ("esc", "cancel")],
+        theme,
+    ));
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Render the browsers to read cookies from, marking the ones found
+fn render_browser_picker(frame: &mut Frame, area: Rect, state: &AppState, index: usize) {
+    let theme = &state.theme;
+    let overlay_area = centered_rect(area, 40, COOKIE_BROWSERS.len() as u16 + 6);
+    frame.render_widget(Clear, overlay_area);
+
+    let block = overlay_block(" cookies from ", theme);
+    let inner = block.inner(overlay_area);
+    frame.render_widget(block, overlay_area);
+
+    let choices = std::iter::once("none").chain(COOKIE_BROWSERS);
+    let mut lines: Vec<Line> = choices
+        .enumerate()
+        .map(|(i, browser)| {
+            let found = if state.installed_browsers.contains(&browser) {
+                "  found"
+            } else {
+                ""
+            };
+            let (marker, style) = if i == index {
+                (
+                    "›",
+                    Style::default()
+                        .fg(theme.accent)
+                        .bg(theme.selection)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                (" ", Style::default().fg(theme.text))
+            };
+            Line::from(vec![
+                Span::styled(format!("{} {}", marker, browser), style),
+                Span::styled(found, Style::default().fg(theme.green)),
+            ])
+        })
+        .collect();
+
+    lines.push(Line::from(""));
+    lines.push(create_shortcuts_line(
+        &[("enter", "use"), ("esc", "cancel")],
+        theme,
+    ));
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Render the prompt asking whether to quit while jobs are running
+fn render_confirm_quit(frame: &mut Frame, area: Rect, state: &AppState) {
+    let theme = &state.theme;
+    let overlay_area = centered_rect(area, 48, 7);
+    frame.render_widget(Clear, overlay_area);
+
+    let block = overlay_block(" quit? ", theme);
+    let inner = block.inner(overlay_area);
+    frame.render_widget(block, overlay_area);
+
+    let active = state.active_jobs_count();
+    let lines = vec![
+        Line::from(Span::styled(
+            format!(
+                "{} {} in progress",
+                active,
+                if active == 1 { "job" } else { "jobs" }
+            ),
+            Style::default().fg(theme.yellow),
+        )),
+        Line::from(Span::styled(
+            if state.config.kill_on_quit {
+                "quitting stops them, they resume on next launch"
+            } else {
+                "carbon quits once they have finished"
+            },
+            Style::default().fg(theme.dim),
+        )),
+        Line::from(""),
+        create_shortcuts_line(&[("q/y", "quit"), ("esc/n", "stay")], theme),
+    ];
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Render the summary of the batch that just finished
+fn render_batch_summary(
+    frame: &mut Frame,
+    area: Rect,
+    completed: usize,
+    failed: usize,
+    elapsed: Duration,
+    theme: &Theme,
+) {
+    let overlay_area = centered_rect(area, 40, 7);
+    frame.render_widget(Clear, overlay_area);
+
+    let block = overlay_block(" all done ", theme);
+    let inner = block.inner(overlay_area);
+    frame.render_widget(block, overlay_area);
+
+    let mut counts = vec![Span::styled(
+        format!("{} completed", completed),
+        Style::default().fg(theme.green),
+    )];
+    if failed > 0 {
+        counts.push(Span::styled(", ", Style::default().fg(theme.dim)));
+        counts.push(Span::styled(
+            format!("{} failed", failed),
+            Style::default().fg(theme.red),
+        ));
+    }
+    let lines = vec![
+        Line::from(counts),
+        Line::from(Span::styled(
+            format!("in {}", format_elapsed(elapsed)),
+            Style::default().fg(theme.dim),
+        )),
+        Line::from(""),
+        create_shortcuts_line(&[("esc", "close")], theme),
+    ];
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Render the list of key bindings
+fn render_help_overlay(frame: &mut Frame, area: Rect, theme: &Theme) {
+    let overlay_area = centered_rect(area, 76, KEY_BINDINGS.len() as u16 + 6);
+    frame.render_widget(Clear, overlay_area);
+
+    let block = overlay_block(" help ", theme);
+    let inner = block.inner(overlay_area);
+    frame.render_widget(block, overlay_area);
+
+    let mut lines: Vec<Line> = KEY_BINDINGS
+        .iter()
+        .map(|(key, description)| {
+            Line::from(vec![
+                Span::styled(format!("{:<10}", key), Style::default().fg(theme.accent)),
+                Span::styled(description.to_string(), Style::default().fg(theme.text)),
+            ])
+        })
+        .collect();
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "letter keys only act as shortcuts while the input is empty",
+        Style::default().fg(theme.dim),
+    )));
+    lines.push(create_shortcuts_line(
+        &[("?", "close"), ("esc", "close")],
+        theme,
+    ));
+
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+}
+
+/// Render the settings editor
+fn render_settings_overlay(
+    frame: &mut Frame,
+    area: Rect,
+    state: &AppState,
+    index: usize,
+    editing: bool,
+) {
+    let theme = &state.theme;
+    let overlay_area = centered_rect(area, 70, Setting::ALL.len() as u16 + 6);
+    frame.render_widget(Clear, overlay_area);
+
+    let block = overlay_block(" settings ", theme);
+    let inner = block.inner(overlay_area);
+    frame.render_widget(block, overlay_area);
+
+    let mut lines: Vec<Line> = Setting::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, setting)| {
+            let selected = i == index;
+            let (marker, row_style) = if selected {
+                ("› ", Style::default().bg(theme.selection))
+            } else {
+                ("  ", Style::default())
+            };
+
+            let value = if selected && editing {
+                Span::styled(
+                    format!("{}_", state.edit_buffer),
+                    Style::default().fg(theme.accent),
+                )
+            } else {
+                Span::styled(
+                    setting.value(&state.config),
+                    Style::default().fg(theme.text),
+                )
+            };
+
+            Line::from(vec![
+                Span::styled(marker, Style::default().fg(theme.accent)),
+                Span::styled(
+                    format!("{:<18}", setting.label()),
+                    Style::default().fg(theme.dim),
+                ),
+                value,
+            ])
+            .style(row_style)
+        })
+        .collect();
+
+    lines.push(Line::from(""));
+    lines.push(if editing {
+        create_shortcuts_line(&[("enter", "save"), ("esc", "discard")], theme)
+    } else {
+        create_shortcuts_line(
+            &[("↑↓", "select"), ("enter", "change"), ("esc", "close")],
+            theme,
+        )
+    });
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Create the bordered block shared by all overlays
+fn overlay_block(title: &str, theme: &Theme) -> Block<'static> {
+    Block::bordered()
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.dim))
+        .title(Span::styled(
+            title.to_string(),
+            Style::default().fg(theme.accent),
+        ))
+        .style(Style::default().bg(theme.bg))
+        .padding(Padding::horizontal(1))
+}
+
 /// Create a text-based progress line
 fn create_progress_line(
-    progress: f64,
-    speed: &Option<String>,
-    eta: &Option<String>,
-    status: &JobStatus,
+    job: &Job,
+    config: &Config,
+    theme: &Theme,
+    tick: usize,
+    width: u16,
 ) -> Line<'static> {
-    let bar_width = 30;
-    let filled = ((progress / 100.0) * bar_width as f64) as usize;
+    let progress = job.progress;
+    let status = &job.status();
+    // Narrow terminals get a shorter bar, the indent and percentage take 11
+    let bar_width = config
+        .progress_bar_width
+        .min((width as usize).saturating_sub(11));
+    let filled = (((progress / 100.0) * bar_width as f64) as usize).min(bar_width);
     let empty = bar_width - filled;
+    let (filled_glyph, empty_glyph) = config.progress_bar_style.glyphs();
 
     let progress_color = match status {
-        JobStatus::Downloading => COLOR_ACCENT,
-        JobStatus::Converting => COLOR_YELLOW,
-        _ => COLOR_DIM,
+        JobStatus::Downloading | JobStatus::Processing => theme.accent,
+        JobStatus::Converting => theme.yellow,
+        _ => theme.dim,
     };
 
-    let mut spans = vec![
-        Span::raw("    "),
-        Span::styled("█".repeat(filled), Style::default().fg(progress_color)),
-        Span::styled("░".repeat(empty), Style::default().fg(COLOR_INPUT_BG)),
-        Span::styled(
-            format!(" {:5.1}%", progress),
-            Style::default().fg(COLOR_TEXT).add_modifier(Modifier::BOLD),
-        ),
-    ];
+    let mut spans = vec![Span::raw("    ")];
+
+    // Probing and conversions of streams without a known length report no
+    // progress, spin until they do. So do yt-dlp's steps after the download,
+    // which would otherwise sit at 100%
+    if let Some(step) = job
+        .postprocess
+        .as_ref()
+        .filter(|_| *status == JobStatus::Processing)
+    {
+        let frame = SPINNER_FRAMES[tick / 2 % SPINNER_FRAMES.len()];
+        spans.push(Span::styled(
+            format!("{} {}…", frame, step),
+            Style::default().fg(progress_color),
+        ));
+    } else if progress == 0.0 && status.is_active() {
+        let frame = SPINNER_FRAMES[tick / 2 % SPINNER_FRAMES.len()];
+        spans.push(Span::styled(
+            format!("{} working...", frame),
+            Style::default().fg(progress_color),
+        ));
+    } else {
+        spans.extend([
+            Span::styled(
+                filled_glyph.repeat(filled),
+                Style::default().fg(progress_color),
+            ),
+            Span::styled(
+                empty_glyph.repeat(empty),
+                Style::default().fg(theme.input_bg),
+            ),
+            Span::styled(
+                format!(" {:5.1}%", progress),
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+            ),
+        ]);
+    }
+
+    // Sizes only make sense while downloading, conversion has its own progress
+    if let (Some(size), JobStatus::Downloading) = (&job.size, status) {
+        spans.push(Span::styled(
+            format!("  {}", downloaded_size(size, progress)),
+            Style::default().fg(theme.text),
+        ));
+    }
 
-    if let Some(s) = speed {
+    if let Some(s) = &job.speed {
         spans.push(Span::styled(
             format!("  {}", s),
-            Style::default().fg(COLOR_DIM),
+            Style::default().fg(theme.dim),
         ));
     }
 
-    if let Some(e) = eta {
+    if let Some(e) = &job.eta {
         spans.push(Span::styled(
             format!("  eta {}", e),
-            Style::default().fg(COLOR_DIM).add_modifier(Modifier::DIM),
+            Style::default().fg(theme.dim).add_modifier(Modifier::DIM),
+        ));
+    }
+
+    if let Some(elapsed) = job.elapsed() {
+        spans.push(Span::styled(
+            format!("  {} elapsed", format_elapsed(elapsed)),
+            Style::default().fg(theme.dim).add_modifier(Modifier::DIM),
         ));
     }
 
     Line::from(spans)
 }
 
+/// Progress of an active job for the end of its line in the compact view,
+/// e.g. "  ████░░░░░░  42%  3.2MiB/s"
+fn create_mini_progress(
+    job: &Job,
+    config: &Config,
+    theme: &Theme,
+    tick: usize,
+) -> Vec<Span<'static>> {
+    const MINI_BAR_WIDTH: usize = 10;
+    let status = job.status();
+    let color = match status {
+        JobStatus::Converting => theme.yellow,
+        _ => theme.accent,
+    };
+    let frame = SPINNER_FRAMES[tick / 2 % SPINNER_FRAMES.len()];
+
+    if let Some(step) = job
+        .postprocess
+        .as_ref()
+        .filter(|_| status == JobStatus::Processing)
+    {
+        return vec![Span::styled(
+            format!("  {} {}…", frame, step),
+            Style::default().fg(color),
+        )];
+    }
+    if job.progress == 0.0 {
+        return vec![Span::styled(
+            format!("  {}", frame),
+            Style::default().fg(color),
+        )];
+    }
+
+    let (filled_glyph, empty_glyph) = config.progress_bar_style.glyphs();
+    let filled = ((job.progress / 100.0 * MINI_BAR_WIDTH as f64) as usize).min(MINI_BAR_WIDTH);
+    let mut spans = vec![
+        Span::styled(
+            format!("  {}", filled_glyph.repeat(filled)),
+            Style::default().fg(color),
+        ),
+        Span::styled(
+            empty_glyph.repeat(MINI_BAR_WIDTH - filled),
+            Style::default().fg(theme.input_bg),
+        ),
+        Span::styled(
+            format!(" {:3.0}%", job.progress),
+            Style::default().fg(theme.text),
+        ),
+    ];
+    if let Some(speed) = job
+        .speed
+        .as_ref()
+        .filter(|_| status == JobStatus::Downloading)
+    {
+        spans.push(Span::styled(
+            format!("  {}", speed),
+            Style::default().fg(theme.dim),
+        ));
+    }
+    spans
+}
+
+/// Format a duration like yt-dlp's eta, e.g. "02:05" or "1:02:05"
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Shorten text longer than `max` characters, keeping its start, e.g.
+/// "A very long vid..."
+fn truncate_end(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    // Without room for the dots
+    if max < 3 {
+        return text.chars().take(max).collect();
+    }
+    let kept: String = text.chars().take(max - 3).collect();
+    format!("{}...", kept)
+}
+
+/// Shorten text longer than `max` characters, keeping its end, e.g.
+/// "...DaVinci/video.mov"
+fn truncate_start(text: &str, max: usize) -> String {
+    let count = text.chars().count();
+    if count <= max {
+        return text.to_string();
+    }
+    if max < 3 {
+        return text.chars().skip(count - max).collect();
+    }
+    let kept: String = text.chars().skip(count - (max - 3)).collect();
+    format!("...{}", kept)
+}
+
+/// Format a byte count in yt-dlp's binary units, e.g. "1.21GiB"
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2}{}", value, UNITS[unit])
+}
+
+/// Format how much of a download is done, e.g. "45.2MiB / 123.45MiB". The
+/// downloaded amount is derived from the progress, in the unit of the total.
+fn downloaded_size(total: &str, progress: f64) -> String {
+    let split = total
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(total.len());
+    let (amount, unit) = total.split_at(split);
+
+    match amount.parse::<f64>() {
+        Ok(amount) => format!("{:.1}{} / {}", amount * progress / 100.0, unit, total),
+        Err(_) => total.to_string(),
+    }
+}
+
 /// Create a shortcuts line
-fn create_shortcuts_line(shortcuts: &[(&str, &str)]) -> Line<'static> {
+fn create_shortcuts_line(shortcuts: &[(&str, &str)], theme: &Theme) -> Line<'static> {
     let mut spans: Vec<Span> = Vec::new();
 
     for (i, (key, desc)) in shortcuts.iter().enumerate() {
         if i > 0 {
-            spans.push(Span::styled(" · ", Style::default().fg(COLOR_DIM)));
+            spans.push(Span::styled(" · ", Style::default().fg(theme.dim)));
         }
         spans.push(Span::styled(
             key.to_string(),
-            Style::default().fg(COLOR_ACCENT),
+            Style::default().fg(theme.accent),
         ));
         spans.push(Span::styled(
             format!(" {}", desc),
-            Style::default().fg(COLOR_DIM),
+            Style::default().fg(theme.dim),
         ));
     }
 
     Line::from(spans)
 }
 
+/// Center a rect within a container, with a max width and height
+fn centered_rect(container: Rect, max_width: u16, max_height: u16) -> Rect {
+    let horizontal = center_horizontally(container, max_width);
+    let height = max_height.min(container.height.saturating_sub(2));
+    let y = container.y + (container.height.saturating_sub(height)) / 2;
+    Rect::new(horizontal.x, y, horizontal.width, height)
+}
+
 /// Center a rect horizontally within a container, with a max width
 fn center_horizontally(container: Rect, max_width: u16) -> Rect {
     let width = max_width.min(container.width.saturating_sub(4));
     let x = container.x + (container.width.saturating_sub(width)) / 2;
     Rect::new(x, container.y, width, container.height)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_end_keeps_short_text() {
+        assert_eq!(truncate_end("", 5), "");
+        assert_eq!(truncate_end("abcde", 5), "abcde");
+    }
+
+    #[test]
+    fn truncate_end_shortens_one_over() {
+        assert_eq!(truncate_end("abcdef", 5), "ab...");
+    }
+
+    #[test]
+    fn truncate_end_without_room_for_dots() {
+        assert_eq!(truncate_end("abcdef", 0), "");
+        assert_eq!(truncate_end("abcdef", 2), "ab");
+        assert_eq!(truncate_end("abcdef", 3), "...");
+    }
+
+    #[test]
+    fn truncate_end_multibyte() {
+        let emoji = "🎵🎶🎸🥁🎹🎺";
+        assert_eq!(truncate_end(emoji, 6), emoji);
+        assert_eq!(truncate_end(emoji, 5), "🎵🎶...");
+        assert_eq!(truncate_end(emoji, 2), "🎵🎶");
+
+        let cjk = "東京の夜景を歩く";
+        assert_eq!(truncate_end(cjk, 8), cjk);
+        assert_eq!(truncate_end(cjk, 7), "東京の夜...");
+        assert_eq!(truncate_end(cjk, 1), "東");
+    }
+
+    #[test]
+    fn truncate_start_keeps_short_text() {
+        assert_eq!(truncate_start("", 5), "");
+        assert_eq!(truncate_start("abcde", 5), "abcde");
+    }
+
+    #[test]
+    fn truncate_start_shortens_one_over() {
+        assert_eq!(truncate_start("abcdef", 5), "...ef");
+    }
+
+    #[test]
+    fn truncate_start_without_room_for_dots() {
+        assert_eq!(truncate_start("abcdef", 0), "");
+        assert_eq!(truncate_start("abcdef", 2), "ef");
+        assert_eq!(truncate_start("abcdef", 3), "...");
+    }
+
+    #[test]
+    fn truncate_start_multibyte() {
+        let emoji = "🎵🎶🎸🥁🎹🎺";
+        assert_eq!(truncate_start(emoji, 6), emoji);
+        assert_eq!(truncate_start(emoji, 5), "...🎹🎺");
+        assert_eq!(truncate_start(emoji, 2), "🎹🎺");
+
+        let cjk = "東京の夜景を歩く";
+        assert_eq!(truncate_start(cjk, 8), cjk);
+        assert_eq!(truncate_start(cjk, 7), "...景を歩く");
+        assert_eq!(truncate_start(cjk, 1), "く");
+    }
+}
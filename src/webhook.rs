@@ -0,0 +1,64 @@
+use crate::config::config_path;
+use crate::history::{json_string, utc_timestamp};
+use crate::models::Job;
+use color_eyre::Result;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+
+/// How long to wait for the webhook before giving up
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// POST a completed or failed job to the webhook as JSON. Runs in the
+/// background, failures are appended to webhook.log next to the config.
+pub fn post_job_finished(webhook_url: &str, job: &Job) {
+    let optional = |value: Option<String>| value.map_or("null".to_string(), |v| json_string(&v));
+    let body = format!(
+        "{{\"id\":{},\"title\":{},\"url\":{},\"status\":{},\"output_path\":{},\"error\":{}}}",
+        json_string(&job.id.to_string()),
+        json_string(&job.display_title()),
+        json_string(&job.url),
+        json_string(job.status().label()),
+        optional(
+            job.output_path
+                .as_ref()
+                .map(|path| path.to_string_lossy().to_string())
+        ),
+        optional(job.error().cloned()),
+    );
+
+    let webhook_url = webhook_url.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = post(&webhook_url, body).await {
+            let _ = log_failure(&webhook_url, &e.to_string());
+        }
+    });
+}
+
+async fn post(webhook_url: &str, body: String) -> Result<()> {
+    reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()?
+        .post(webhook_url)
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+fn log_failure(webhook_url: &str, error: &str) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(config_path()?.with_file_name("webhook.log"))?;
+    writeln!(
+        file,
+        "{} POST {} failed: {}",
+        utc_timestamp(SystemTime::now()),
+        webhook_url,
+        error
+    )?;
+    Ok(())
+}